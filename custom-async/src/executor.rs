@@ -1,13 +1,12 @@
 use core::{
     future::Future,
     pin::Pin,
-    sync::atomic::{AtomicUsize, Ordering},
-    task::{Context, RawWaker, RawWakerVTable, Waker},
+    sync::atomic::{AtomicU32, AtomicUsize, Ordering},
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
 };
 
 use cortex_m::asm;
 use defmt::{debug, error, info};
-use heapless::mpmc::Queue;
 
 pub trait ExtWaker {
     fn task_id(&self) -> usize;
@@ -45,38 +44,110 @@ unsafe fn wake_by_ref(p: *const ()) {
     wake_task(p as usize);
 }
 
+// Waking a task that is ready already changes nothing, so any number of
+// wakes fit.
 pub fn wake_task(task_id: usize) {
     debug!("EXECUTOR: waking task {}", task_id);
-    if TASK_ID_READY.enqueue(task_id).is_err() {
-        // Being unable to wake a task will likely cause it to become
-        // permanently unresponsive.
-        panic!("Task queue full: can't add task {}", task_id);
+    if task_id >= MAX_TASKS {
+        error!("EXECUTOR: bad task id {}", task_id);
+        return;
     }
+    // no atomic read-modify-write on the M0+, and the ISRs wake tasks too
+    critical_section::with(|_| {
+        READY.store(
+            READY.load(Ordering::Relaxed) | 1 << task_id,
+            Ordering::Relaxed,
+        );
+    });
 }
 
-static TASK_ID_READY: Queue<usize, 4> = Queue::new();
+// Returns the tasks woken since the last call.
+fn take_ready() -> u32 {
+    critical_section::with(|_| {
+        let ready = READY.load(Ordering::Relaxed);
+        READY.store(0, Ordering::Relaxed);
+        ready
+    })
+}
+
+// one bit per task that waits to be polled
+static READY: AtomicU32 = AtomicU32::new(0);
 static NUM_TASKS: AtomicUsize = AtomicUsize::new(0);
 
+const MAX_TASKS: usize = u32::BITS as usize;
+
+// statistics, only written by the executor loop
+static POLLS: [AtomicU32; MAX_TASKS] = [const { AtomicU32::new(0) }; MAX_TASKS];
+static FINISHED: AtomicU32 = AtomicU32::new(0);
+static IDLE_COUNT: AtomicU32 = AtomicU32::new(0);
+
+pub fn num_tasks() -> usize {
+    NUM_TASKS.load(Ordering::Relaxed)
+}
+
+// How often the task was polled and whether it has finished.
+pub fn task_stats(task_id: usize) -> Option<(u32, bool)> {
+    if task_id >= num_tasks() {
+        return None;
+    }
+    let polls = POLLS[task_id].load(Ordering::Relaxed);
+    let finished = FINISHED.load(Ordering::Relaxed) & (1 << task_id) != 0;
+    Some((polls, finished))
+}
+
+// How often the executor went to sleep for lack of ready tasks.
+pub fn idle_count() -> u32 {
+    IDLE_COUNT.load(Ordering::Relaxed)
+}
+
 pub fn run_tasks(tasks: &mut [Pin<&mut dyn Future<Output = ()>>]) -> ! {
+    if tasks.len() > MAX_TASKS {
+        panic!("Too many tasks: {} > {}", tasks.len(), MAX_TASKS);
+    }
     NUM_TASKS.store(tasks.len(), Ordering::Relaxed);
 
+    // a finished future must never be polled again, even if a stale waker fires
+    let mut finished: u32 = 0;
+
     // everybody gets one run to start...
     for task_id in 0..tasks.len() {
-        TASK_ID_READY.enqueue(task_id).ok();
+        wake_task(task_id);
     }
 
     loop {
-        while let Some(task_id) = TASK_ID_READY.dequeue() {
+        let mut ready = take_ready();
+        while ready != 0 {
+            // lowest task id first, tasks woken meanwhile wait for the next round
+            let task_id = ready.trailing_zeros() as usize;
+            ready &= ready - 1;
             if task_id >= tasks.len() {
                 error!("EXECUTOR: bad task id {}", task_id);
                 continue;
             }
+            if finished & (1 << task_id) != 0 {
+                debug!("EXECUTOR: task {} already finished", task_id);
+                continue;
+            }
             debug!("EXECUTOR: running task {}", task_id);
-            let _ = tasks[task_id]
+            let polls = &POLLS[task_id];
+            polls.store(
+                polls.load(Ordering::Relaxed).wrapping_add(1),
+                Ordering::Relaxed,
+            );
+            let poll = tasks[task_id]
                 .as_mut()
                 .poll(&mut Context::from_waker(&get_waker(task_id)));
+            if poll == Poll::Ready(()) {
+                info!("EXECUTOR: task {} finished", task_id);
+                finished |= 1 << task_id;
+                FINISHED.store(finished, Ordering::Relaxed);
+            }
+            if ready == 0 {
+                ready = take_ready();
+            }
         }
         info!("EXECUTOR: no tasks ready, going to sleep...");
+        IDLE_COUNT.store(idle_count().wrapping_add(1), Ordering::Relaxed);
         asm::wfi();
     }
 }
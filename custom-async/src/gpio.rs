@@ -5,33 +5,77 @@ use bsp::hal::{
     pac::{self, interrupt},
 };
 use core::{
+    cell::RefCell,
     future::poll_fn,
+    pin::Pin,
     sync::atomic::{AtomicUsize, Ordering},
-    task::Poll,
+    task::{Context, Poll},
 };
-use defmt::{debug, info};
+use critical_section::Mutex;
+use defmt::{Format, debug, info};
 use embedded_hal::digital::{InputPin, PinState};
+use futures::Stream;
+use heapless::Deque;
+use pico_app::runtime::{EdgeInput, Input};
 
 use crate::button::ButtonPin;
 use crate::executor::{ExtWaker, wake_task};
+use crate::time::{Instant, Ticker};
 
-const MAX_BUTTONS: usize = 2;
+// two buttons, the two encoder contacts and the fan tachometer or IR receiver
+const MAX_BUTTONS: usize = 5;
 const INVALID_TASK_ID: usize = usize::MAX;
 const INVALID_GPIO: usize = usize::MAX;
+// edges kept per pin until a task takes them, the oldest are dropped first
+const EDGE_BUFFER_SIZE: usize = 8;
 
-static WAKE_TASKS: [AtomicUsize; MAX_BUTTONS] = [
-    AtomicUsize::new(INVALID_TASK_ID),
-    AtomicUsize::new(INVALID_TASK_ID),
-];
+type EdgeBuffer = Deque<(Edge, Instant), EDGE_BUFFER_SIZE>;
 
-static GPIO_PINS: [AtomicUsize; MAX_BUTTONS] = [
-    AtomicUsize::new(INVALID_GPIO),
-    AtomicUsize::new(INVALID_GPIO),
-];
+static WAKE_TASKS: [AtomicUsize; MAX_BUTTONS] =
+    [const { AtomicUsize::new(INVALID_TASK_ID) }; MAX_BUTTONS];
+
+static GPIO_PINS: [AtomicUsize; MAX_BUTTONS] =
+    [const { AtomicUsize::new(INVALID_GPIO) }; MAX_BUTTONS];
+
+static EDGES: Mutex<RefCell<[EdgeBuffer; MAX_BUTTONS]>> =
+    Mutex::new(RefCell::new([const { Deque::new() }; MAX_BUTTONS]));
+
+#[derive(Clone, Copy, Debug, Format, PartialEq, Eq)]
+pub enum Edge {
+    Rising,
+    Falling,
+}
+
+impl Edge {
+    // The level the pin is at after the edge.
+    pub fn state(self) -> PinState {
+        match self {
+            Edge::Rising => PinState::High,
+            Edge::Falling => PinState::Low,
+        }
+    }
+}
+
+fn push_edge(index: usize, edge: Edge, at: Instant) {
+    critical_section::with(|cs| {
+        let edges = &mut EDGES.borrow_ref_mut(cs)[index];
+        if edges.is_full() {
+            debug!("GPIO INTERRUPT: edge buffer {} full, drop oldest", index);
+            edges.pop_front();
+        }
+        edges.push_back((edge, at)).ok();
+    });
+}
+
+fn pop_edge(index: usize) -> Option<(Edge, Instant)> {
+    critical_section::with(|cs| EDGES.borrow_ref_mut(cs)[index].pop_front())
+}
 
 pub struct InputChannel {
     pin: ButtonPin,
     index: usize,
+    // when the pin reached the state `wait_for` last returned on
+    changed_at: Option<Instant>,
 }
 
 impl InputChannel {
@@ -45,37 +89,116 @@ impl InputChannel {
         };
 
         GPIO_PINS[index].store(pin.id().num as usize, Ordering::Relaxed);
+        critical_section::with(|cs| EDGES.borrow_ref_mut(cs)[index].clear());
 
         pin.set_interrupt_enabled(EdgeLow, true);
         pin.set_interrupt_enabled(EdgeHigh, true);
 
         unsafe { pac::NVIC::unmask(pac::Interrupt::IO_IRQ_BANK0) }
 
-        Self { pin, index }
+        Self {
+            pin,
+            index,
+            changed_at: None,
+        }
     }
 
-    pub async fn wait_for(&mut self, ready_state: PinState) {
-        poll_fn(|cx| {
-            let current_state = if self.pin.is_low().unwrap() {
-                PinState::Low
-            } else {
-                PinState::High
-            };
+    pub fn gpio(&self) -> u8 {
+        self.pin.id().num
+    }
+
+    fn state(&mut self) -> PinState {
+        if self.pin.is_low().unwrap() {
+            PinState::Low
+        } else {
+            PinState::High
+        }
+    }
+
+    // Waits for the next edge the ISR recorded, with the time it happened.
+    // Edges are buffered from the moment the channel is created, and
+    // `wait_for` takes them from the same buffer.
+    pub async fn next_edge(&mut self) -> (Edge, Instant) {
+        poll_fn(|cx| self.poll_edge(cx)).await
+    }
+
+    fn poll_edge(&mut self, cx: &mut Context<'_>) -> Poll<(Edge, Instant)> {
+        // register first so an edge between the pop and the store isn't lost
+        WAKE_TASKS[self.index].store(cx.waker().task_id(), Ordering::Relaxed);
+        match pop_edge(self.index) {
+            Some((edge, at)) => {
+                WAKE_TASKS[self.index].store(INVALID_TASK_ID, Ordering::Relaxed);
+                debug!("INPUT CHANNEL: edge {} at {} us", edge, at.ticks());
+                Poll::Ready((edge, at))
+            }
+            None => Poll::Pending,
+        }
+    }
 
-            if ready_state == current_state {
+    // Forgets the edges recorded so far.
+    #[cfg(feature = "tacho")]
+    pub fn clear_edges(&mut self) {
+        critical_section::with(|cs| EDGES.borrow_ref_mut(cs)[self.index].clear());
+    }
+
+    // Waits until the pin is in `ready_state`. The first edge into that
+    // state tells when it was reached, later ones are contact bounce.
+    pub async fn wait_for(&mut self, ready_state: PinState) {
+        self.changed_at = None;
+        loop {
+            while let Some((edge, at)) = pop_edge(self.index) {
+                self.note_edge(edge, at, ready_state);
+            }
+            if self.state() == ready_state {
                 debug!("INPUT CHANNEL: pin in ready state");
-                Poll::Ready(())
-            } else {
-                let task_id = cx.waker().task_id();
-                debug!(
-                    "INPUT CHANNEL: pin not ready, store pending task with id = {}",
-                    task_id
-                );
-                WAKE_TASKS[self.index].store(task_id, Ordering::Relaxed);
-                Poll::Pending
+                return;
             }
-        })
-        .await
+            debug!("INPUT CHANNEL: pin not ready, wait for the next edge");
+            let (edge, at) = self.next_edge().await;
+            self.note_edge(edge, at, ready_state);
+        }
+    }
+
+    fn note_edge(&mut self, edge: Edge, at: Instant, ready_state: PinState) {
+        if edge.state() == ready_state && self.changed_at.is_none() {
+            self.changed_at = Some(at);
+        }
+    }
+}
+
+impl Input for InputChannel {
+    async fn wait_for_low(&mut self) {
+        self.wait_for(PinState::Low).await
+    }
+
+    async fn wait_for_high(&mut self) {
+        self.wait_for(PinState::High).await
+    }
+
+    fn is_high(&mut self) -> bool {
+        self.state() == PinState::High
+    }
+
+    fn changed_ms(&self) -> Option<u32> {
+        self.changed_at
+            .map(|at| at.duration_since_epoch().to_millis() as u32)
+    }
+}
+
+impl EdgeInput for InputChannel {
+    async fn next_edge(&mut self) -> (bool, u32) {
+        let (edge, at) = InputChannel::next_edge(self).await;
+        (edge == Edge::Rising, at.ticks() as u32)
+    }
+}
+
+// Yields the edges the ISR recorded, the same ones `next_edge` returns,
+// and never ends.
+impl Stream for InputChannel {
+    type Item = (Edge, Instant);
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<(Edge, Instant)>> {
+        self.poll_edge(cx).map(Some)
     }
 }
 
@@ -83,9 +206,12 @@ impl InputChannel {
 fn IO_IRQ_BANK0() {
     info!("GPIO INTERRUPT: button press detected!");
 
+    let now = Ticker::now();
     // SAFETY: only accessed in IRQ
     let io = unsafe { &*pac::IO_BANK0::ptr() };
     let ints1 = io.proc0_ints(1).read().bits();
+    // SAFETY: read-only access to the input levels
+    let levels = unsafe { (*pac::SIO::ptr()).gpio_in().read().bits() };
 
     for (i, task) in WAKE_TASKS.iter().enumerate() {
         debug!("GPIO INTERRUPT: i = {}", i);
@@ -104,6 +230,24 @@ fn IO_IRQ_BANK0() {
             debug!("GPIO INTERRUPT: pin {} had edge change!", gpio);
             io.intr(1).write(|w| unsafe { w.bits(clear_mask) });
 
+            // both edges since the last interrupt: the current level tells
+            // which came last, both get the same timestamp
+            let falling = clear_mask & low_mask != 0;
+            let rising = clear_mask & high_mask != 0;
+            let high = levels & (1 << gpio) != 0;
+            match (falling, rising) {
+                (true, true) if high => {
+                    push_edge(i, Edge::Falling, now);
+                    push_edge(i, Edge::Rising, now);
+                }
+                (true, true) => {
+                    push_edge(i, Edge::Rising, now);
+                    push_edge(i, Edge::Falling, now);
+                }
+                (true, false) => push_edge(i, Edge::Falling, now),
+                (false, _) => push_edge(i, Edge::Rising, now),
+            }
+
             let task_id = task.load(Ordering::Relaxed);
             task.store(INVALID_TASK_ID, Ordering::Relaxed);
 
@@ -0,0 +1,33 @@
+use pico_app::console::{Stats, SystemInfo, TaskInfo};
+
+use crate::executor;
+use crate::time::Ticker;
+use crate::{uart, usb};
+
+// What a console reports on `stats` and `tasks`, with the byte counters of
+// the port it runs on.
+pub enum Info {
+    Uart,
+    Usb,
+}
+
+impl SystemInfo for Info {
+    fn stats(&self) -> Stats {
+        let (rx_bytes, tx_bytes, rx_dropped) = match self {
+            Info::Uart => uart::counters(),
+            Info::Usb => usb::counters(),
+        };
+        Stats {
+            uptime_ms: Ticker::now().duration_since_epoch().to_millis() as u32,
+            idle_count: Some(executor::idle_count()),
+            rx_bytes,
+            tx_bytes,
+            rx_dropped,
+        }
+    }
+
+    fn task(&self, id: usize) -> Option<TaskInfo> {
+        let (polls, finished) = executor::task_stats(id)?;
+        Some(TaskInfo { polls, finished })
+    }
+}
@@ -6,26 +6,91 @@ use panic_probe as _;
 
 use rp_pico as bsp;
 
+#[cfg(all(feature = "ir", feature = "tacho"))]
+compile_error!("the `ir` and `tacho` features share GPIO14, enable only one of them");
+
+// the LED modes, only a WS2812 strip can play the animations as well
+#[cfg(any(
+    all(
+        feature = "breathe",
+        any(
+            feature = "animation",
+            feature = "matrix",
+            feature = "ws2812",
+            feature = "pong",
+            feature = "reaction"
+        )
+    ),
+    all(
+        feature = "animation",
+        any(feature = "matrix", feature = "pong", feature = "reaction")
+    ),
+    all(
+        feature = "matrix",
+        any(feature = "ws2812", feature = "pong", feature = "reaction")
+    ),
+    all(feature = "ws2812", any(feature = "pong", feature = "reaction")),
+    all(feature = "pong", feature = "reaction"),
+))]
+compile_error!(
+    "enable at most one of the LED modes `breathe`, `animation`, `matrix`, `ws2812`, `pong` \
+     and `reaction`, or `ws2812` together with `animation`"
+);
+
 mod button;
-mod channel;
 mod executor;
 mod gpio;
+mod info;
 mod led;
+#[cfg(feature = "tacho")]
+mod measure;
+#[cfg(any(
+    feature = "breathe",
+    all(feature = "animation", not(feature = "ws2812"))
+))]
+mod pwm;
 mod time;
+mod uart;
+mod usb;
+#[cfg(feature = "ws2812")]
+mod ws2812;
 
 use bsp::entry;
 use bsp::hal::{Watchdog, clocks::init_clocks_and_plls, pac, sio};
-use core::pin::pin;
+use cfg_if::cfg_if;
+use core::pin::{Pin, pin};
 use defmt::{debug, info};
-use embedded_hal::digital::PinState;
-use futures::{FutureExt, select_biased};
+use heapless::Vec;
+use pico_app::channel::Channel;
+#[cfg(feature = "encoder")]
+use pico_app::encoder::{Acceleration, Detent, Encoder, QuadratureDecoder};
+use pico_app::input::{Directions, InputEvent, KEYMAP};
+#[cfg(feature = "ir")]
+use pico_app::ir::{IR_KEYMAP, NecDecoder};
+#[cfg(feature = "keypad")]
+use pico_app::keypad::{KEYPAD_4X4, Keypad};
+use pico_app::led::LedControl;
+use pico_app::oneshot::Oneshot;
+#[cfg(feature = "remote")]
+use pico_app::remote::{self, EventLog, Tap};
+#[cfg(feature = "encoder")]
+use pico_app::runtime::Input;
+use pico_app::tasks;
 use time::Ticker;
 
-use crate::button::{ButtonDirection, ButtonPin};
-use crate::channel::{Channel, Receiver, Sender};
 use crate::gpio::InputChannel;
-use crate::led::{LedPin, LedRow, NUM_LEDS};
-use crate::time::Duration;
+use crate::info::Info;
+use crate::led::LedPin;
+use crate::time::TimerDelay;
+use crate::uart::Uart;
+use crate::usb::UsbSerial;
+
+#[cfg(feature = "matrix")]
+const MESSAGES: [&str; 3] = ["HELLO PICO!", "ASYNC RUST", "0123456789"];
+#[cfg(feature = "matrix")]
+const MATRIX_REFRESH_HZ: u32 = 100;
+#[cfg(feature = "ws2812")]
+const STRIP_LENGTH: usize = 10;
 
 #[entry]
 fn main() -> ! {
@@ -55,7 +120,7 @@ fn main() -> ! {
         &mut pac.RESETS,
     );
 
-    let leds: [LedPin; NUM_LEDS] = [
+    let leds: [LedPin; 10] = [
         pins.gpio16.into_push_pull_output().into_dyn_pin(),
         pins.gpio17.into_push_pull_output().into_dyn_pin(),
         pins.gpio18.into_push_pull_output().into_dyn_pin(),
@@ -68,57 +133,294 @@ fn main() -> ! {
         pins.gpio28.into_push_pull_output().into_dyn_pin(),
     ];
 
-    let button_l = pins.gpio10.into_pull_up_input().into_dyn_pin();
-    let button_r = pins.gpio11.into_pull_up_input().into_dyn_pin();
+    let button_l = InputChannel::new(pins.gpio10.into_pull_up_input().into_dyn_pin());
+    let button_r = InputChannel::new(pins.gpio11.into_pull_up_input().into_dyn_pin());
+    let left = KEYMAP
+        .button(button_l.gpio())
+        .expect("GPIO10 not in keymap");
+    let right = KEYMAP
+        .button(button_r.gpio())
+        .expect("GPIO11 not in keymap");
+
+    // rotary encoder, its contacts close to ground like the buttons
+    #[cfg(feature = "encoder")]
+    let mut encoder_a = InputChannel::new(pins.gpio12.into_pull_up_input().into_dyn_pin());
+    #[cfg(feature = "encoder")]
+    let mut encoder_b = InputChannel::new(pins.gpio13.into_pull_up_input().into_dyn_pin());
+    #[cfg(feature = "encoder")]
+    let encoder = Encoder::new(
+        QuadratureDecoder::new(Detent::Full, encoder_a.is_high(), encoder_b.is_high()),
+        Acceleration::default(),
+    );
+
+    let channel: Channel<InputEvent> = Channel::new();
+    // with `remote` the host sees the input events the LED task gets
+    #[cfg(feature = "remote")]
+    let events = EventLog::new();
+    #[cfg(feature = "remote")]
+    let receiver = Tap::new(channel.get_receiver(), &events);
+    #[cfg(not(feature = "remote"))]
+    let receiver = channel.get_receiver();
+
+    // lets the serial console steer the blinking LED modes, the others
+    // ignore it
+    let active_led = Oneshot::new();
+    #[cfg(not(feature = "ws2812"))]
+    let control = LedControl::new(leds.len(), tasks::BLINK_PERIOD_MS, &active_led);
+    #[cfg(feature = "ws2812")]
+    let control = LedControl::new(STRIP_LENGTH, tasks::BLINK_PERIOD_MS, &active_led);
+
+    // the LED mode picks what shows on the LEDs and the tasks that drive them
+    cfg_if! {
+        if #[cfg(feature = "matrix")] {
+            use core::cell::RefCell;
+            use pico_app::FrameBuffer;
+            use pico_app::matrix::{self, ScanDisplay};
+            use crate::time::Duration;
+
+            let frame = RefCell::new(FrameBuffer::<5, 5>::new());
+            cfg_if! {
+                if #[cfg(feature = "charlieplex")] {
+                    // an LED for every ordered pair of the ten pins, scanned one
+                    // anode at a time
+                    let display = matrix::Charlieplex::new(leds);
+                } else {
+                    // the ten LED pins become the five rows and five columns of a 5×5 matrix
+                    let [r0, r1, r2, r3, r4, c0, c1, c2, c3, c4] = leds;
+                    let display =
+                        matrix::LedMatrix::new([r0, r1, r2, r3, r4], [c0, c1, c2, c3, c4]);
+                }
+            }
+            let lines = ScanDisplay::<5, 5>::lines(&display);
+            let display_task = pin!(tasks::matrix_task(
+                &frame,
+                display,
+                time::interval(Duration::micros(
+                    matrix::line_period_us(MATRIX_REFRESH_HZ, lines) as u64,
+                )),
+            ));
+            let led_task = pin!(tasks::text_task(
+                &frame,
+                &MESSAGES,
+                Directions(receiver),
+                TimerDelay,
+            ));
+            let mode_tasks: [Task; 2] = [led_task, display_task];
+        } else if #[cfg(feature = "ws2812")] {
+            use bsp::hal::{Clock, dma::DMAExt};
+            use core::cell::RefCell;
+            use pico_app::ws2812::{ColorStrip, Rgb};
+            use crate::ws2812::Ws2812;
+
+            // the strip on GPIO15 takes over from the discrete LEDs, which stay off
+            let _ = leds;
+            let strip = RefCell::new(ColorStrip::<STRIP_LENGTH>::new(Rgb::CYAN));
+            let display_task = pin!(tasks::strip_task(
+                &strip,
+                Ws2812::new(
+                    pins.gpio15.into_push_pull_output().into_dyn_pin(),
+                    pac.PIO0,
+                    pac.DMA.split(&mut pac.RESETS).ch0,
+                    cortex_m::singleton!(: [u32; STRIP_LENGTH] = [0; STRIP_LENGTH]).unwrap(),
+                    clocks.system_clock.freq().to_Hz(),
+                    &mut pac.RESETS,
+                ),
+                TimerDelay,
+            ));
+            cfg_if! {
+                if #[cfg(feature = "animation")] {
+                    let led_task = pin!(tasks::animation_task(
+                        &strip,
+                        Directions(receiver),
+                        TimerDelay,
+                    ));
+                    let mode_tasks: [Task; 2] = [led_task, display_task];
+                } else {
+                    use pico_app::LedRow;
+                    use pico_app::sync::Mutex;
 
-    let channel: Channel<ButtonDirection> = Channel::new();
-    let led_task = pin!(led_task(leds, channel.get_receiver()));
-    let button_l_task = pin!(button_task(
+                    let row = Mutex::new(LedRow::new(pico_app::ws2812::pixel_pins(&strip)));
+                    let led_task = pin!(tasks::led_task(&row, Directions(receiver), &control));
+                    let blink_task = pin!(tasks::blink_task(&row, TimerDelay, &control));
+                    let mode_tasks: [Task; 3] = [led_task, blink_task, display_task];
+                }
+            }
+        } else if #[cfg(feature = "pong")] {
+            use pico_app::LedRow;
+            use pico_app::game::Pong;
+
+            let led_task = pin!(tasks::game_task(
+                Pong::<10>::new(),
+                LedRow::new(leds),
+                Directions(receiver),
+                TimerDelay,
+                TimerDelay,
+            ));
+            let mode_tasks: [Task; 1] = [led_task];
+        } else if #[cfg(feature = "reaction")] {
+            use pico_app::LedRow;
+            use pico_app::game::ReactionTimer;
+
+            let led_task = pin!(tasks::game_task(
+                ReactionTimer::new(Ticker::now().ticks() as u32),
+                LedRow::new(leds),
+                Directions(receiver),
+                TimerDelay,
+                TimerDelay,
+            ));
+            let mode_tasks: [Task; 1] = [led_task];
+        } else if #[cfg(feature = "animation")] {
+            use pico_app::PwmLedRow;
+            use crate::pwm::PwmLed;
+
+            pwm::init(pac.PWM, &mut pac.RESETS);
+            let led_task = pin!(tasks::animation_task(
+                PwmLedRow::new(leds.map(PwmLed::new)),
+                Directions(receiver),
+                TimerDelay,
+            ));
+            let mode_tasks: [Task; 1] = [led_task];
+        } else if #[cfg(feature = "breathe")] {
+            use pico_app::PwmLedRow;
+            use crate::pwm::PwmLed;
+
+            pwm::init(pac.PWM, &mut pac.RESETS);
+            let led_task = pin!(tasks::breathing_led_task(
+                PwmLedRow::new(leds.map(PwmLed::new)),
+                Directions(receiver),
+                TimerDelay,
+            ));
+            let mode_tasks: [Task; 1] = [led_task];
+        } else {
+            use pico_app::LedRow;
+            use pico_app::sync::Mutex;
+
+            // the LED task moves the selection, the blink task blinks it
+            let row = Mutex::new(LedRow::new(leds));
+            let led_task = pin!(tasks::led_task(&row, Directions(receiver), &control));
+            let blink_task = pin!(tasks::blink_task(&row, TimerDelay, &control));
+            let mode_tasks: [Task; 2] = [led_task, blink_task];
+        }
+    }
+
+    let button_l_task = pin!(tasks::input_task(
         button_l,
-        ButtonDirection::Left,
+        left,
         channel.get_sender(),
+        TimerDelay,
+        TimerDelay,
     ));
-    let button_r_task = pin!(button_task(
+    let button_r_task = pin!(tasks::input_task(
         button_r,
-        ButtonDirection::Right,
+        right,
         channel.get_sender(),
+        TimerDelay,
+        TimerDelay,
     ));
 
-    debug!("Initialization complete, run tasks...");
-    executor::run_tasks(&mut [led_task, button_l_task, button_r_task]);
-}
+    #[cfg(feature = "encoder")]
+    let encoder_task = pin!(tasks::encoder_task(
+        encoder_a,
+        encoder_b,
+        encoder,
+        channel.get_sender(),
+        TimerDelay,
+    ));
 
-async fn led_task(leds: [LedPin; NUM_LEDS], mut receiver: Receiver<'_, ButtonDirection>) {
-    debug!("LED TASK: called!");
-    let mut blinker = LedRow::new(leds);
-    loop {
-        debug!("LED TASK: toggle led");
-        blinker.toggle();
-        select_biased! {
-            direction = receiver.receive().fuse() => {
-                debug!("LED TASK: shift led");
-                blinker.shift(direction);
-            }
-            _ = time::delay(Duration::millis(500)).fuse() => {}
-        }
+    // 4×4 keypad: rows on GPIO2-5 are driven, columns on GPIO6-9 are read.
+    // Unlike the buttons the columns aren't `InputChannel`s: a column only
+    // means something right after its row was driven low, so the scan reads
+    // them directly, and edge interrupts would fire on every scan of a held
+    // key. Polling also leaves the `InputChannel` slots to the pins above.
+    #[cfg(feature = "keypad")]
+    let keypad_task = pin!(tasks::keypad_task(
+        Keypad::new(
+            [
+                pins.gpio2.into_push_pull_output().into_dyn_pin(),
+                pins.gpio3.into_push_pull_output().into_dyn_pin(),
+                pins.gpio4.into_push_pull_output().into_dyn_pin(),
+                pins.gpio5.into_push_pull_output().into_dyn_pin(),
+            ],
+            [
+                pins.gpio6.into_pull_up_input().into_dyn_pin(),
+                pins.gpio7.into_pull_up_input().into_dyn_pin(),
+                pins.gpio8.into_pull_up_input().into_dyn_pin(),
+                pins.gpio9.into_pull_up_input().into_dyn_pin(),
+            ],
+            TimerDelay,
+        ),
+        KEYPAD_4X4,
+        channel.get_sender(),
+        TimerDelay,
+        TimerDelay,
+    ));
+
+    // IR receiver module, its output idles high
+    #[cfg(feature = "ir")]
+    let ir_task = pin!(tasks::ir_task(
+        InputChannel::new(pins.gpio14.into_pull_up_input().into_dyn_pin()),
+        NecDecoder::new(),
+        &IR_KEYMAP,
+        channel.get_sender(),
+        TimerDelay,
+    ));
+
+    #[cfg(feature = "tacho")]
+    let tacho_task = pin!(measure::tacho_task(InputChannel::new(
+        pins.gpio14.into_pull_up_input().into_dyn_pin(),
+    )));
+
+    // command console on UART0, TX on GPIO0 and RX on GPIO1
+    let uart = Uart::new(
+        pac.UART0,
+        (pins.gpio0.into_function(), pins.gpio1.into_function()),
+        &mut pac.RESETS,
+        &clocks,
+    );
+    let console_task = pin!(tasks::console_task(uart, &control, &Info::Uart));
+
+    // the same console on the USB port, no probe or UART adapter needed, or
+    // the binary protocol for `pico-ctl` with `remote`
+    let usb = UsbSerial::new(
+        pac.USBCTRL_REGS,
+        pac.USBCTRL_DPRAM,
+        clocks.usb_clock,
+        &mut pac.RESETS,
+    );
+    #[cfg(not(feature = "remote"))]
+    let usb_console_task = pin!(tasks::console_task(usb, &control, &Info::Usb));
+    #[cfg(feature = "remote")]
+    let usb_console_task = pin!(remote::remote_task(usb, &control, &Info::Usb, &events));
+
+    // the optional tasks only join the list when their feature is enabled
+    let mut tasks: Tasks = Vec::new();
+    for task in mode_tasks {
+        add_task(&mut tasks, task);
     }
+    add_task(&mut tasks, button_l_task);
+    add_task(&mut tasks, button_r_task);
+    #[cfg(feature = "encoder")]
+    add_task(&mut tasks, encoder_task);
+    add_task(&mut tasks, console_task);
+    add_task(&mut tasks, usb_console_task);
+    #[cfg(feature = "keypad")]
+    add_task(&mut tasks, keypad_task);
+    #[cfg(feature = "ir")]
+    add_task(&mut tasks, ir_task);
+    #[cfg(feature = "tacho")]
+    add_task(&mut tasks, tacho_task);
+
+    debug!("Initialization complete, run tasks...");
+    executor::run_tasks(&mut tasks);
 }
 
-async fn button_task(
-    pin: ButtonPin,
-    direction: ButtonDirection,
-    sender: Sender<'_, ButtonDirection>,
-) {
-    debug!("BUTTON TASK {}: called!", direction);
-    let mut input = InputChannel::new(pin);
-    loop {
-        debug!("BUTTON TASK {}: wait for input...", direction);
-        input.wait_for(PinState::Low).await;
-        debug!("BUTTON TASK {}: send direction", direction);
-        sender.send(direction);
-        debug!("BUTTON TASK {}: debounce delay", direction);
-        time::delay(Duration::millis(150)).await;
-        debug!("BUTTON TASK {}: wait for high pin state...", direction);
-        input.wait_for(PinState::High).await;
-    }
+type Task<'a> = Pin<&'a mut dyn Future<Output = ()>>;
+// room for every task of the largest feature set
+type Tasks<'a> = Vec<Task<'a>, 12>;
+
+fn add_task<'a>(tasks: &mut Tasks<'a>, task: Task<'a>) {
+    tasks
+        .push(task)
+        .map_err(drop)
+        .expect("more tasks than the task list has room for");
 }
@@ -0,0 +1,122 @@
+use rp_pico as bsp;
+
+use bsp::hal::fugit::HertzU32;
+use defmt::{debug, info};
+use embedded_hal::digital::PinState;
+use futures::{FutureExt, select_biased};
+
+use crate::gpio::{Edge, InputChannel};
+use crate::time::{Duration, Timer};
+
+// fans report two pulses per revolution on their tachometer line
+const TACHO_PULSES_PER_REVOLUTION: u32 = 2;
+const TACHO_WINDOW_MS: u64 = 1000;
+
+// Pulse, period and frequency measurement from the edge timestamps of the
+// GPIO ISR. Every edge costs an interrupt, which is fine for fan tachometers
+// and IR remotes up to a few kHz.
+impl InputChannel {
+    // Waits for the pin to go to `level` and back and returns how long it
+    // stayed there. Edges from before the call are ignored.
+    pub async fn measure_pulse(&mut self, level: PinState) -> Duration {
+        self.clear_edges();
+        let start = loop {
+            let (edge, at) = self.next_edge().await;
+            if edge.state() == level {
+                break at;
+            }
+        };
+        loop {
+            let (edge, at) = self.next_edge().await;
+            if edge.state() != level {
+                let width = at - start;
+                debug!("MEASURE: pulse of {} us", width.ticks());
+                return width;
+            }
+        }
+    }
+
+    // Averages the time between rising edges during `window`. Timing runs
+    // from the first to the last edge seen, so a partial period at either end
+    // doesn't skew the result. `None` without a full period or when the
+    // periods are shorter than the 1 us timer resolution.
+    pub async fn measure_period(&mut self, window: Duration) -> Option<Duration> {
+        self.clear_edges();
+        let mut timer = Timer::new(window).fuse();
+        let mut first = None;
+        let mut last = None;
+        let mut periods: u64 = 0;
+        loop {
+            let (edge, at) = select_biased! {
+                edge = self.next_edge().fuse() => edge,
+                _ = timer => break,
+            };
+            if edge != Edge::Rising {
+                continue;
+            }
+            if first.is_none() {
+                first = Some(at);
+            } else {
+                periods += 1;
+                last = Some(at);
+            }
+        }
+
+        let period = match (first, last) {
+            (Some(first), Some(last)) if last > first => Some((last - first).ticks() / periods)
+                .filter(|&us| us > 0)
+                .map(Duration::micros),
+            _ => None,
+        };
+        debug!(
+            "MEASURE: {} periods of {} us",
+            periods,
+            period.map(|period| period.ticks())
+        );
+        period
+    }
+
+    // The frequency of the rising edges during `window`, 0 Hz without a full
+    // period. The tacho task goes by the period, which resolves slow signals
+    // better than whole hertz.
+    #[allow(dead_code)]
+    pub async fn measure_frequency(&mut self, window: Duration) -> HertzU32 {
+        let hz = self
+            .measure_period(window)
+            .await
+            .and_then(|period| 1_000_000u64.checked_div(period.ticks()))
+            .unwrap_or(0);
+        HertzU32::from_raw(hz as u32)
+    }
+}
+
+// Logs the speed of a PC fan once per measuring window. The speed comes from
+// the period in microseconds, whole hertz would only resolve 30 rpm. A
+// healthy tachometer line is low for about half of every period.
+pub async fn tacho_task(mut input: InputChannel) {
+    debug!("TACHO TASK: called!");
+    let window = Duration::millis(TACHO_WINDOW_MS);
+    loop {
+        let Some(period) = input.measure_period(window).await else {
+            info!("TACHO TASK: 0 rpm");
+            continue;
+        };
+        let revolution_us = period.ticks() * TACHO_PULSES_PER_REVOLUTION as u64;
+        let Some(rpm) = 60_000_000u64.checked_div(revolution_us) else {
+            continue;
+        };
+        let low = select_biased! {
+            width = input.measure_pulse(PinState::Low).fuse() => Some(width),
+            _ = Timer::new(Duration::micros(3 * period.ticks())).fuse() => None,
+        };
+        match low {
+            Some(low) => info!(
+                "TACHO TASK: {} rpm, period {} us, low for {} us",
+                rpm,
+                period.ticks(),
+                low.ticks()
+            ),
+            None => info!("TACHO TASK: {} rpm, period {} us", rpm, period.ticks()),
+        }
+    }
+}
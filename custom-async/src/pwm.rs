@@ -0,0 +1,91 @@
+use rp_pico as bsp;
+
+use bsp::hal::{
+    gpio::{DynPinId, FunctionPwm, Pin, PullDown},
+    pac,
+    pwm::Slices,
+};
+use core::convert::Infallible;
+use defmt::debug;
+use embedded_hal::pwm::{ErrorType, SetDutyCycle};
+
+use crate::led::LedPin;
+
+pub type PwmPin = Pin<DynPinId, FunctionPwm, PullDown>;
+
+// One count short of the maximum so that a compare value of `u16::MAX`
+// keeps the output high for the whole period.
+const TOP: u16 = u16::MAX - 1;
+
+pub fn init(pwm: pac::PWM, resets: &mut pac::RESETS) {
+    // only needed to bring the PWM block out of reset, the slices are
+    // configured per pin below
+    Slices::new(pwm, resets).free();
+}
+
+enum Channel {
+    A,
+    B,
+}
+
+pub struct PwmLed {
+    _pin: PwmPin,
+    slice: usize,
+    channel: Channel,
+}
+
+impl PwmLed {
+    pub fn new(pin: LedPin) -> Self {
+        // GPIO n is wired to channel A/B (even/odd) of slice (n / 2) % 8
+        let gpio = pin.id().num as usize;
+        let slice = (gpio / 2) % 8;
+        let channel = if gpio.is_multiple_of(2) {
+            Channel::A
+        } else {
+            Channel::B
+        };
+
+        let pin: PwmPin = match pin.try_into_function() {
+            Ok(pin) => pin,
+            Err(_) => panic!("GPIO {} can't be used for PWM", gpio),
+        };
+
+        debug!("PWM LED: GPIO {} on slice {}", gpio, slice);
+        let ch = regs().ch(slice);
+        ch.top().write(|w| unsafe { w.top().bits(TOP) });
+        ch.csr().modify(|_, w| w.en().set_bit());
+
+        let mut led = Self {
+            _pin: pin,
+            slice,
+            channel,
+        };
+        led.set_duty_cycle(0).ok();
+        led
+    }
+}
+
+fn regs() -> &'static pac::pwm::RegisterBlock {
+    // SAFETY: each PwmLed only touches the compare value of its own channel;
+    // TOP and enable are written with the same values for both channels
+    unsafe { &*pac::PWM::ptr() }
+}
+
+impl ErrorType for PwmLed {
+    type Error = Infallible;
+}
+
+impl SetDutyCycle for PwmLed {
+    fn max_duty_cycle(&self) -> u16 {
+        TOP + 1
+    }
+
+    fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Self::Error> {
+        let cc = regs().ch(self.slice).cc();
+        match self.channel {
+            Channel::A => cc.modify(|_, w| unsafe { w.a().bits(duty) }),
+            Channel::B => cc.modify(|_, w| unsafe { w.b().bits(duty) }),
+        };
+        Ok(())
+    }
+}
@@ -7,10 +7,17 @@ use bsp::hal::{
     pac::{self, interrupt},
     timer::Alarm,
 };
-use core::{cell::RefCell, task::Poll};
+use core::{
+    cell::RefCell,
+    pin::Pin,
+    task::{Context, Poll},
+};
 use critical_section::Mutex;
 use defmt::{debug, info};
+use embedded_hal::delay::DelayNs;
+use futures::{Stream, StreamExt};
 use heapless::Vec;
+use pico_app::runtime::{Clock, Delay, Tick};
 
 use crate::executor::{ExtWaker, wake_task};
 
@@ -21,7 +28,7 @@ static NEXT_DEADLINES: Mutex<RefCell<Vec<(u64, usize), 8>>> = Mutex::new(RefCell
 
 enum TimerState {
     Init,
-    Wait,
+    Wait { task_id: usize },
 }
 
 pub struct Timer {
@@ -31,8 +38,12 @@ pub struct Timer {
 
 impl Timer {
     pub fn new(duration: Duration) -> Self {
+        Self::at(Ticker::now() + duration)
+    }
+
+    pub fn at(end_time: Instant) -> Self {
         Self {
-            end_time: Ticker::now() + duration,
+            end_time,
             state: TimerState::Init,
         }
     }
@@ -72,17 +83,17 @@ impl Timer {
 
 impl Future for Timer {
     type Output = ();
-    fn poll(
-        mut self: core::pin::Pin<&mut Self>,
-        cx: &mut core::task::Context<'_>,
-    ) -> core::task::Poll<Self::Output> {
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         match self.state {
+            // a deadline in the past would never fire the alarm
+            TimerState::Init if Ticker::now() >= self.end_time => Poll::Ready(()),
             TimerState::Init => {
-                self.register(cx.waker().task_id());
-                self.state = TimerState::Wait;
+                let task_id = cx.waker().task_id();
+                self.register(task_id);
+                self.state = TimerState::Wait { task_id };
                 Poll::Pending
             }
-            TimerState::Wait => {
+            TimerState::Wait { .. } => {
                 if Ticker::now() >= self.end_time {
                     Poll::Ready(())
                 } else {
@@ -93,10 +104,70 @@ impl Future for Timer {
     }
 }
 
+// A timer dropped before its deadline, say the losing side of a select, takes
+// its deadline along. The alarm may still fire for it, the interrupt then has
+// nobody to wake and just schedules the next deadline.
+impl Drop for Timer {
+    fn drop(&mut self) {
+        let TimerState::Wait { task_id } = self.state else {
+            return;
+        };
+        let deadline = (self.end_time.duration_since_epoch().ticks(), task_id);
+        critical_section::with(|cs| {
+            let mut deadlines = NEXT_DEADLINES.borrow_ref_mut(cs);
+            // already gone if the deadline has passed
+            if let Some(index) = deadlines.iter().position(|&entry| entry == deadline) {
+                deadlines.swap_remove(index);
+            }
+        });
+    }
+}
+
 pub async fn delay(duration: Duration) {
     Timer::new(duration).await;
 }
 
+pub struct TimerDelay;
+
+impl Delay for TimerDelay {
+    async fn delay_ms(&mut self, millis: u32) {
+        delay(Duration::millis(millis as u64)).await
+    }
+}
+
+// Busy waits, meant for the few microseconds hardware needs to settle.
+impl DelayNs for TimerDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        let end = Ticker::now() + Duration::micros(ns.div_ceil(1000) as u64);
+        while Ticker::now() < end {}
+    }
+}
+
+impl Clock for TimerDelay {
+    fn now_ms(&self) -> u32 {
+        Ticker::now().duration_since_epoch().to_millis() as u32
+    }
+}
+
+// A fixed-rate timer, as a `Stream` of ticks or behind `Tick`. So far only
+// the scanning of the LED matrix runs on one.
+#[cfg_attr(not(feature = "matrix"), allow(dead_code))]
+pub struct Interval {
+    period: Duration,
+    next: Instant,
+    timer: Timer,
+}
+
+#[cfg_attr(not(feature = "matrix"), allow(dead_code))]
+pub fn interval(period: Duration) -> Interval {
+    let next = Ticker::now() + period;
+    Interval {
+        period,
+        next,
+        timer: Timer::at(next),
+    }
+}
+
 static TICKER: Mutex<RefCell<Option<Ticker>>> = Mutex::new(RefCell::new(None));
 
 pub struct Ticker {
@@ -168,3 +239,30 @@ fn TIMER_IRQ_0() {
         }
     });
 }
+
+// Yields the instant each tick was due, it never ends.
+impl Stream for Interval {
+    type Item = Instant;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Instant>> {
+        if Pin::new(&mut self.timer).poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+        let tick = self.next;
+        let now = Ticker::now();
+        // skip missed ticks instead of firing a burst of them
+        self.next = if tick + self.period > now {
+            tick + self.period
+        } else {
+            now + self.period
+        };
+        self.timer = Timer::at(self.next);
+        Poll::Ready(Some(tick))
+    }
+}
+
+impl Tick for Interval {
+    async fn tick(&mut self) {
+        self.next().await;
+    }
+}
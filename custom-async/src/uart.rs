@@ -0,0 +1,196 @@
+use rp_pico as bsp;
+
+use bsp::hal::{
+    Clock as _,
+    clocks::ClocksManager,
+    fugit::RateExtU32,
+    gpio::{
+        FunctionUart, Pin, PullDown,
+        bank0::{Gpio0, Gpio1},
+    },
+    pac::{self, interrupt},
+    uart::{DataBits, Enabled, StopBits, UartConfig, UartPeripheral},
+};
+use core::{
+    cell::RefCell,
+    future::poll_fn,
+    sync::atomic::{AtomicU32, AtomicUsize, Ordering},
+    task::Poll,
+};
+use critical_section::Mutex;
+use defmt::{debug, info};
+use heapless::Deque;
+use pico_app::runtime::Serial;
+
+use crate::executor::{ExtWaker, wake_task};
+
+pub const BAUD_RATE: u32 = 115_200;
+const RX_BUFFER_SIZE: usize = 64;
+const TX_BUFFER_SIZE: usize = 256;
+const INVALID_TASK_ID: usize = usize::MAX;
+
+pub type UartPins = (
+    Pin<Gpio0, FunctionUart, PullDown>,
+    Pin<Gpio1, FunctionUart, PullDown>,
+);
+type Device = UartPeripheral<Enabled, pac::UART0, UartPins>;
+
+static UART: Mutex<RefCell<Option<Device>>> = Mutex::new(RefCell::new(None));
+static RX: Mutex<RefCell<Deque<u8, RX_BUFFER_SIZE>>> = Mutex::new(RefCell::new(Deque::new()));
+static TX: Mutex<RefCell<Deque<u8, TX_BUFFER_SIZE>>> = Mutex::new(RefCell::new(Deque::new()));
+
+static RX_TASK: AtomicUsize = AtomicUsize::new(INVALID_TASK_ID);
+static TX_TASK: AtomicUsize = AtomicUsize::new(INVALID_TASK_ID);
+
+// only changed inside critical sections, no read-modify-write atomics needed
+static RX_BYTES: AtomicU32 = AtomicU32::new(0);
+static TX_BYTES: AtomicU32 = AtomicU32::new(0);
+static RX_DROPPED: AtomicU32 = AtomicU32::new(0);
+
+fn count(counter: &AtomicU32) {
+    counter.store(
+        counter.load(Ordering::Relaxed).wrapping_add(1),
+        Ordering::Relaxed,
+    );
+}
+
+// Received, sent and dropped bytes since boot.
+pub fn counters() -> (u32, u32, u32) {
+    (
+        RX_BYTES.load(Ordering::Relaxed),
+        TX_BYTES.load(Ordering::Relaxed),
+        RX_DROPPED.load(Ordering::Relaxed),
+    )
+}
+
+// Interrupt-driven UART0 on GPIO0 (TX) and GPIO1 (RX), 8N1. The ISR moves
+// bytes between the FIFOs and the ring buffers and wakes the reading or
+// writing task. There is only one UART0, so there is only one `Uart`.
+pub struct Uart {
+    _private: (),
+}
+
+impl Uart {
+    pub fn new(
+        uart: pac::UART0,
+        pins: UartPins,
+        resets: &mut pac::RESETS,
+        clocks: &ClocksManager,
+    ) -> Self {
+        let config = UartConfig::new(BAUD_RATE.Hz(), DataBits::Eight, None, StopBits::One);
+        let mut device = UartPeripheral::new(uart, pins, resets)
+            .enable(config, clocks.peripheral_clock.freq())
+            .unwrap();
+        device.enable_rx_interrupt();
+
+        critical_section::with(|cs| UART.borrow_ref_mut(cs).replace(device));
+        unsafe { pac::NVIC::unmask(pac::Interrupt::UART0_IRQ) }
+
+        info!("UART: running at {} baud", BAUD_RATE);
+        Self { _private: () }
+    }
+}
+
+// Fills the TX FIFO from the buffer. The PL011 only interrupts when the FIFO
+// level drops past the watermark, so writers have to prime it themselves.
+fn pump(device: &mut Device, tx: &mut Deque<u8, TX_BUFFER_SIZE>) -> bool {
+    let mut sent = false;
+    while device.uart_is_writable() {
+        let Some(byte) = tx.pop_front() else {
+            break;
+        };
+        device.write_raw(&[byte]).ok();
+        count(&TX_BYTES);
+        sent = true;
+    }
+    if tx.is_empty() {
+        device.disable_tx_interrupt();
+    } else {
+        device.enable_tx_interrupt();
+    }
+    sent
+}
+
+impl Serial for Uart {
+    async fn read(&mut self, buffer: &mut [u8]) -> usize {
+        poll_fn(|cx| {
+            // register first so a byte between the pop and the store isn't lost
+            RX_TASK.store(cx.waker().task_id(), Ordering::Relaxed);
+            let len = critical_section::with(|cs| {
+                let mut rx = RX.borrow_ref_mut(cs);
+                let mut len = 0;
+                while len < buffer.len()
+                    && let Some(byte) = rx.pop_front()
+                {
+                    buffer[len] = byte;
+                    len += 1;
+                }
+                len
+            });
+            if len == 0 {
+                return Poll::Pending;
+            }
+            RX_TASK.store(INVALID_TASK_ID, Ordering::Relaxed);
+            debug!("UART: read {} bytes", len);
+            Poll::Ready(len)
+        })
+        .await
+    }
+
+    async fn write(&mut self, mut data: &[u8]) {
+        poll_fn(|cx| {
+            TX_TASK.store(cx.waker().task_id(), Ordering::Relaxed);
+            critical_section::with(|cs| {
+                let mut tx = TX.borrow_ref_mut(cs);
+                while let Some((&byte, rest)) = data.split_first()
+                    && tx.push_back(byte).is_ok()
+                {
+                    data = rest;
+                }
+                pump(UART.borrow_ref_mut(cs).as_mut().unwrap(), &mut tx);
+            });
+            if !data.is_empty() {
+                debug!("UART: TX buffer full, {} bytes waiting", data.len());
+                return Poll::Pending;
+            }
+            TX_TASK.store(INVALID_TASK_ID, Ordering::Relaxed);
+            Poll::Ready(())
+        })
+        .await
+    }
+}
+
+#[interrupt]
+fn UART0_IRQ() {
+    debug!("UART INTERRUPT: called");
+
+    let (received, sent) = critical_section::with(|cs| {
+        let mut device = UART.borrow_ref_mut(cs);
+        let device = device.as_mut().unwrap();
+
+        let mut rx = RX.borrow_ref_mut(cs);
+        let mut received = false;
+        let mut byte = [0];
+        while device.uart_is_readable() {
+            // framing or parity errors still take the byte out of the FIFO
+            match device.read_raw(&mut byte) {
+                Ok(1) if rx.push_back(byte[0]).is_ok() => {
+                    count(&RX_BYTES);
+                    received = true;
+                }
+                _ => count(&RX_DROPPED),
+            }
+        }
+
+        let sent = pump(device, &mut TX.borrow_ref_mut(cs));
+        (received, sent)
+    });
+
+    for (ready, task) in [(received, &RX_TASK), (sent, &TX_TASK)] {
+        let task_id = task.load(Ordering::Relaxed);
+        if ready && task_id != INVALID_TASK_ID {
+            task.store(INVALID_TASK_ID, Ordering::Relaxed);
+            wake_task(task_id);
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use rp_pico as bsp;
+
+use bsp::hal::{
+    clocks::UsbClock,
+    pac::{self, interrupt},
+    usb::UsbBus,
+};
+use core::{
+    cell::RefCell,
+    future::poll_fn,
+    sync::atomic::{AtomicU32, AtomicUsize, Ordering},
+    task::Poll,
+};
+use critical_section::Mutex;
+use defmt::{debug, info};
+use pico_app::runtime::Serial;
+use usb_device::{
+    UsbError,
+    class_prelude::UsbBusAllocator,
+    device::{StringDescriptors, UsbDevice, UsbDeviceBuilder, UsbDeviceState, UsbVidPid},
+};
+use usbd_serial::{SerialPort, USB_CLASS_CDC};
+
+use crate::executor::{ExtWaker, wake_task};
+
+// pid.codes test VID/PID, fine for a hobby board that never leaves the desk
+const VID_PID: UsbVidPid = UsbVidPid(0x1209, 0x0001);
+const INVALID_TASK_ID: usize = usize::MAX;
+
+struct Usb {
+    device: UsbDevice<'static, UsbBus>,
+    serial: SerialPort<'static, UsbBus>,
+}
+
+static USB: Mutex<RefCell<Option<Usb>>> = Mutex::new(RefCell::new(None));
+
+static RX_TASK: AtomicUsize = AtomicUsize::new(INVALID_TASK_ID);
+static TX_TASK: AtomicUsize = AtomicUsize::new(INVALID_TASK_ID);
+
+// only changed inside critical sections, no read-modify-write atomics needed
+static RX_BYTES: AtomicU32 = AtomicU32::new(0);
+static TX_BYTES: AtomicU32 = AtomicU32::new(0);
+
+fn count(counter: &AtomicU32, bytes: usize) {
+    counter.store(
+        counter.load(Ordering::Relaxed).wrapping_add(bytes as u32),
+        Ordering::Relaxed,
+    );
+}
+
+// Received, sent and dropped bytes since boot. Nothing gets dropped, the
+// host holds back what the serial class has no room for.
+pub fn counters() -> (u32, u32, u32) {
+    (
+        RX_BYTES.load(Ordering::Relaxed),
+        TX_BYTES.load(Ordering::Relaxed),
+        0,
+    )
+}
+
+// The RP2040 enumerates as a CDC-ACM serial port. The USB ISR services the
+// device and wakes the tasks reading from and writing to the port. Like
+// `Uart`, there is only one.
+pub struct UsbSerial {
+    _private: (),
+}
+
+impl UsbSerial {
+    pub fn new(
+        regs: pac::USBCTRL_REGS,
+        dpram: pac::USBCTRL_DPRAM,
+        clock: UsbClock,
+        resets: &mut pac::RESETS,
+    ) -> Self {
+        let bus = UsbBus::new(regs, dpram, clock, true, resets);
+        let allocator: &'static UsbBusAllocator<UsbBus> =
+            cortex_m::singleton!(: UsbBusAllocator<UsbBus> = UsbBusAllocator::new(bus))
+                .expect("USB already set up");
+
+        let serial = SerialPort::new(allocator);
+        let device = UsbDeviceBuilder::new(allocator, VID_PID)
+            .strings(&[StringDescriptors::default()
+                .manufacturer("pico-async-rust")
+                .product("custom-async console")
+                .serial_number("0001")])
+            .unwrap()
+            .device_class(USB_CLASS_CDC)
+            .build();
+
+        critical_section::with(|cs| USB.borrow_ref_mut(cs).replace(Usb { device, serial }));
+        unsafe { pac::NVIC::unmask(pac::Interrupt::USBCTRL_IRQ) }
+
+        info!("USB: waiting for the host");
+        Self { _private: () }
+    }
+}
+
+impl Serial for UsbSerial {
+    async fn read(&mut self, buffer: &mut [u8]) -> usize {
+        poll_fn(|cx| {
+            // register first so data arriving in between isn't lost
+            RX_TASK.store(cx.waker().task_id(), Ordering::Relaxed);
+            let result = critical_section::with(|cs| {
+                let result = USB.borrow_ref_mut(cs).as_mut().unwrap().serial.read(buffer);
+                if let Ok(len) = result {
+                    count(&RX_BYTES, len);
+                }
+                result
+            });
+            match result {
+                Ok(len) if len > 0 => {
+                    RX_TASK.store(INVALID_TASK_ID, Ordering::Relaxed);
+                    debug!("USB: read {} bytes", len);
+                    Poll::Ready(len)
+                }
+                _ => Poll::Pending,
+            }
+        })
+        .await
+    }
+
+    async fn write(&mut self, mut data: &[u8]) {
+        poll_fn(|cx| {
+            TX_TASK.store(cx.waker().task_id(), Ordering::Relaxed);
+            let done = critical_section::with(|cs| {
+                let mut usb = USB.borrow_ref_mut(cs);
+                let usb = usb.as_mut().unwrap();
+                // without a host nobody reads it, don't hold up the console
+                if usb.device.state() != UsbDeviceState::Configured {
+                    return true;
+                }
+                while !data.is_empty() {
+                    match usb.serial.write(data) {
+                        Ok(len) => {
+                            count(&TX_BYTES, len);
+                            data = &data[len..];
+                        }
+                        Err(UsbError::WouldBlock) => return false,
+                        Err(_) => return true,
+                    }
+                }
+                usb.serial.flush().ok();
+                true
+            });
+            if !done {
+                return Poll::Pending;
+            }
+            TX_TASK.store(INVALID_TASK_ID, Ordering::Relaxed);
+            Poll::Ready(())
+        })
+        .await
+    }
+}
+
+#[interrupt]
+fn USBCTRL_IRQ() {
+    debug!("USB INTERRUPT: called");
+
+    critical_section::with(|cs| {
+        let mut usb = USB.borrow_ref_mut(cs);
+        let usb = usb.as_mut().unwrap();
+        let Usb { device, serial } = usb;
+        device.poll(&mut [serial]);
+    });
+
+    // received data as well as room in the IN buffer can be why we're here
+    for task in [&RX_TASK, &TX_TASK] {
+        let task_id = task.load(Ordering::Relaxed);
+        if task_id != INVALID_TASK_ID {
+            task.store(INVALID_TASK_ID, Ordering::Relaxed);
+            wake_task(task_id);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use rp_pico as bsp;
+
+use bsp::hal::{
+    dma::{CH0, Channel, SingleChannel, single_buffer},
+    gpio::{DynPinId, FunctionPio0, Pin, PullDown},
+    pac::{self, interrupt},
+    pio::{Buffers, PIOBuilder, PIOExt, PinDir, SM0, ShiftDirection, Tx},
+};
+use core::{
+    future::poll_fn,
+    sync::atomic::{AtomicUsize, Ordering},
+    task::Poll,
+};
+use defmt::debug;
+use pico_app::ws2812::{RESET_US, StripWriter, clock_divider, frame_time_us};
+
+use crate::executor::{ExtWaker, wake_task};
+use crate::led::LedPin;
+use crate::time::{Duration, delay};
+
+pub type StripPin = Pin<DynPinId, FunctionPio0, PullDown>;
+type StripTx = Tx<(pac::PIO0, SM0)>;
+
+const INVALID_TASK_ID: usize = usize::MAX;
+// words the TX FIFO and the output shift register still hold once the DMA
+// transfer is done
+const FIFO_DEPTH: usize = 5;
+
+static WAKE_TASK: AtomicUsize = AtomicUsize::new(INVALID_TASK_ID);
+
+pub struct Ws2812<const N: usize> {
+    _pin: StripPin,
+    // handed to the DMA transfer while a frame is being sent
+    parts: Option<(Channel<CH0>, &'static mut [u32; N], StripTx)>,
+}
+
+impl<const N: usize> Ws2812<N> {
+    pub fn new(
+        pin: LedPin,
+        pio: pac::PIO0,
+        mut dma: Channel<CH0>,
+        buffer: &'static mut [u32; N],
+        sys_clock_hz: u32,
+        resets: &mut pac::RESETS,
+    ) -> Self {
+        let gpio = pin.id().num;
+        let pin: StripPin = match pin.try_into_function() {
+            Ok(pin) => pin,
+            Err(_) => panic!("GPIO {} can't be used for PIO0", gpio),
+        };
+
+        // one bit takes T3 + T1 + T2 cycles, see `pico_app::ws2812`
+        let program = pio_proc::pio_asm!(
+            ".side_set 1",
+            ".wrap_target",
+            "bitloop:",
+            "    out x, 1       side 0 [2]",
+            "    jmp !x do_zero side 1 [1]",
+            "do_one:",
+            "    jmp bitloop    side 1 [4]",
+            "do_zero:",
+            "    nop            side 0 [4]",
+            ".wrap",
+        );
+
+        let (mut pio, sm0, _, _, _) = pio.split(resets);
+        let installed = pio.install(&program.program).unwrap();
+        let (int, frac) = clock_divider(sys_clock_hz);
+        let (mut sm, _, tx) = PIOBuilder::from_installed_program(installed)
+            .side_set_pin_base(gpio)
+            .out_shift_direction(ShiftDirection::Left)
+            .autopull(true)
+            .pull_threshold(24)
+            .buffers(Buffers::OnlyTx)
+            .clock_divisor_fixed_point(int, frac)
+            .build(sm0);
+        sm.set_pindirs([(gpio, PinDir::Output)]);
+        sm.start();
+
+        dma.enable_irq0();
+        unsafe { pac::NVIC::unmask(pac::Interrupt::DMA_IRQ_0) }
+
+        debug!("WS2812: {} pixels on GPIO {}", N, gpio);
+        Self {
+            _pin: pin,
+            parts: Some((dma, buffer, tx)),
+        }
+    }
+}
+
+impl<const N: usize> StripWriter<N> for Ws2812<N> {
+    async fn write(&mut self, words: &[u32; N]) {
+        let (dma, buffer, tx) = self
+            .parts
+            .take()
+            .expect("WS2812: previous write was canceled mid-transfer");
+        buffer.copy_from_slice(words);
+
+        let transfer = single_buffer::Config::new(dma, buffer, tx).start();
+        poll_fn(|cx| {
+            // register first so the interrupt can't fire in between
+            WAKE_TASK.store(cx.waker().task_id(), Ordering::Relaxed);
+            if transfer.is_done() {
+                WAKE_TASK.store(INVALID_TASK_ID, Ordering::Relaxed);
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await;
+        self.parts = Some(transfer.wait());
+
+        // let the FIFO drain, then keep the line low long enough to latch
+        let drain_us = frame_time_us(FIFO_DEPTH.min(N));
+        delay(Duration::micros((drain_us + RESET_US) as u64)).await;
+    }
+}
+
+#[interrupt]
+fn DMA_IRQ_0() {
+    // SAFETY: only the interrupt status of channel 0 is touched, and only here
+    let dma = unsafe { &*pac::DMA::ptr() };
+    dma.ints0().write(|w| unsafe { w.bits(1 << 0) });
+
+    let task_id = WAKE_TASK.load(Ordering::Relaxed);
+    WAKE_TASK.store(INVALID_TASK_ID, Ordering::Relaxed);
+    if task_id != INVALID_TASK_ID {
+        debug!("DMA INTERRUPT: wake task with ID = {}", task_id);
+        wake_task(task_id);
+    }
+}
@@ -1,27 +1,59 @@
 #![no_std]
 #![no_main]
 
-mod button;
-mod led;
+mod runtime;
 
-use crate::led::LedRow;
-use button::ButtonDirection;
 use defmt::{info, panic};
 use embassy_executor::Spawner;
-use embassy_rp::gpio::{self, Input, Output};
+use embassy_futures::join::join4;
+use embassy_rp::gpio::{self, Input, Output, Pin};
+#[cfg(feature = "ir")]
+use embassy_rp::peripherals::PIO0;
+use embassy_rp::peripherals::USB;
+#[cfg(feature = "ir")]
+use embassy_rp::pio::{self, Pio};
+use embassy_rp::{bind_interrupts, usb};
 use embassy_sync::{blocking_mutex::raw::ThreadModeRawMutex, channel::Channel};
-use embassy_time::Timer;
-use futures::{FutureExt, select_biased};
+use embassy_usb::class::cdc_acm::{CdcAcmClass, State};
+use pico_app::channel::CHANNEL_SIZE;
+#[cfg(feature = "encoder")]
+use pico_app::encoder::{Acceleration, Detent, Encoder, QuadratureDecoder};
+use pico_app::input::{Button, Directions, InputEvent, KEYMAP};
+#[cfg(feature = "ir")]
+use pico_app::ir::{IR_KEYMAP, NecDecoder};
+#[cfg(feature = "keypad")]
+use pico_app::keypad::{KEYPAD_4X4, Keypad};
+use pico_app::led::LedControl;
+use pico_app::oneshot::Oneshot;
+#[cfg(feature = "remote")]
+use pico_app::remote::{self, EventLog, Tap};
+use pico_app::sync::Mutex;
+use pico_app::{LedRow, tasks};
+
+#[cfg(feature = "ir")]
+use crate::runtime::PulseCapture;
+use crate::runtime::{ButtonInput, ChannelReceiver, ChannelSender, Info, TimerDelay, UsbSerial};
 
 use {defmt_rtt as _, panic_probe as _};
 
-static CHANNEL: Channel<ThreadModeRawMutex, ButtonDirection, 1> = Channel::new();
+bind_interrupts!(struct Irqs {
+    USBCTRL_IRQ => usb::InterruptHandler<USB>;
+    #[cfg(feature = "ir")]
+    PIO0_IRQ_0 => pio::InterruptHandler<PIO0>;
+});
+
+// one input task per keymap entry
+const BUTTONS: usize = KEYMAP.len();
+
+static CHANNEL: Channel<ThreadModeRawMutex, InputEvent, CHANNEL_SIZE> = Channel::new();
 
 #[embassy_executor::main]
 async fn main(spawner: Spawner) {
     info!("Starting...");
     let p = embassy_rp::init(Default::default());
 
+    let left = KEYMAP.button(p.PIN_10.pin()).expect("GPIO10 not in keymap");
+    let right = KEYMAP.button(p.PIN_11.pin()).expect("GPIO11 not in keymap");
     let button_l = Input::new(p.PIN_10, gpio::Pull::Up);
     let button_r = Input::new(p.PIN_11, gpio::Pull::Up);
 
@@ -38,32 +70,135 @@ async fn main(spawner: Spawner) {
         Output::new(p.PIN_28, gpio::Level::Low),
     ];
 
+    spawner.spawn(input_task(button_l, left)).unwrap();
+    spawner.spawn(input_task(button_r, right)).unwrap();
+
+    // rotary encoder, its contacts close to ground like the buttons
+    #[cfg(feature = "encoder")]
     spawner
-        .spawn(button_task(button_l, ButtonDirection::Left))
+        .spawn(encoder_task(
+            Input::new(p.PIN_12, gpio::Pull::Up),
+            Input::new(p.PIN_13, gpio::Pull::Up),
+        ))
         .unwrap();
+
+    // IR receiver module, its output idles high
+    #[cfg(feature = "ir")]
     spawner
-        .spawn(button_task(button_r, ButtonDirection::Right))
+        .spawn(ir_task(PulseCapture::new(Pio::new(p.PIO0, Irqs), p.PIN_14)))
         .unwrap();
 
-    let mut blinker = LedRow::new(leds);
-
-    loop {
-        blinker.toggle();
-        select_biased! {
-            direction = CHANNEL.receive().fuse() => {
-                blinker.shift(direction);
-            }
-            _ = Timer::after_millis(500).fuse() => {}
-        }
+    // 4×4 keypad: rows on GPIO2-5 are driven, columns on GPIO6-9 are read
+    #[cfg(feature = "keypad")]
+    {
+        let rows = [
+            Output::new(p.PIN_2, gpio::Level::High),
+            Output::new(p.PIN_3, gpio::Level::High),
+            Output::new(p.PIN_4, gpio::Level::High),
+            Output::new(p.PIN_5, gpio::Level::High),
+        ];
+        let columns = [
+            Input::new(p.PIN_6, gpio::Pull::Up),
+            Input::new(p.PIN_7, gpio::Pull::Up),
+            Input::new(p.PIN_8, gpio::Pull::Up),
+            Input::new(p.PIN_9, gpio::Pull::Up),
+        ];
+        spawner.spawn(keypad_task(rows, columns)).unwrap();
     }
+
+    // USB serial console (or the `remote` protocol), runs next to the LED task so both can share
+    // `control`
+    let mut config = embassy_usb::Config::new(0x1209, 0x0001);
+    config.manufacturer = Some("pico-async-rust");
+    config.product = Some("embassy-async console");
+    config.serial_number = Some("0001");
+    let mut config_descriptor = [0; 256];
+    let mut bos_descriptor = [0; 256];
+    let mut control_buffer = [0; 64];
+    let mut state = State::new();
+    let mut builder = embassy_usb::Builder::new(
+        usb::Driver::new(p.USB, Irqs),
+        config,
+        &mut config_descriptor,
+        &mut bos_descriptor,
+        &mut [],
+        &mut control_buffer,
+    );
+    let serial = CdcAcmClass::new(&mut builder, &mut state, 64);
+    let mut usb = builder.build();
+
+    let active_led = Oneshot::new();
+    let control = LedControl::new(leds.len(), tasks::BLINK_PERIOD_MS, &active_led);
+    // with `remote` the host sees the input events the LED task gets
+    #[cfg(feature = "remote")]
+    let events = EventLog::new();
+    #[cfg(feature = "remote")]
+    let receiver = Tap::new(ChannelReceiver(CHANNEL.receiver()), &events);
+    #[cfg(not(feature = "remote"))]
+    let receiver = ChannelReceiver(CHANNEL.receiver());
+    #[cfg(not(feature = "remote"))]
+    let serial_task = tasks::console_task(UsbSerial(serial), &control, &Info);
+    #[cfg(feature = "remote")]
+    let serial_task = remote::remote_task(UsbSerial(serial), &control, &Info, &events);
+    // the LED task moves the selection, the blink task blinks it
+    let row = Mutex::new(LedRow::new(leds));
+    join4(
+        tasks::led_task(&row, Directions(receiver), &control),
+        tasks::blink_task(&row, TimerDelay, &control),
+        usb.run(),
+        serial_task,
+    )
+    .await;
 }
 
-#[embassy_executor::task(pool_size = 2)]
-async fn button_task(mut pin: Input<'static>, direction: ButtonDirection) {
-    loop {
-        pin.wait_for_low().await;
-        CHANNEL.send(direction).await;
-        Timer::after_millis(200).await;
-        pin.wait_for_high().await;
-    }
+#[embassy_executor::task(pool_size = BUTTONS)]
+async fn input_task(pin: Input<'static>, button: Button) {
+    tasks::input_task(
+        ButtonInput(pin),
+        button,
+        ChannelSender(CHANNEL.sender()),
+        TimerDelay,
+        TimerDelay,
+    )
+    .await
+}
+
+#[cfg(feature = "encoder")]
+#[embassy_executor::task]
+async fn encoder_task(a: Input<'static>, b: Input<'static>) {
+    let decoder = QuadratureDecoder::new(Detent::Full, a.is_high(), b.is_high());
+    tasks::encoder_task(
+        ButtonInput(a),
+        ButtonInput(b),
+        Encoder::new(decoder, Acceleration::default()),
+        ChannelSender(CHANNEL.sender()),
+        TimerDelay,
+    )
+    .await
+}
+
+#[cfg(feature = "keypad")]
+#[embassy_executor::task]
+async fn keypad_task(rows: [Output<'static>; 4], columns: [Input<'static>; 4]) {
+    tasks::keypad_task(
+        Keypad::new(rows, columns, embassy_time::Delay),
+        KEYPAD_4X4,
+        ChannelSender(CHANNEL.sender()),
+        TimerDelay,
+        TimerDelay,
+    )
+    .await
+}
+
+#[cfg(feature = "ir")]
+#[embassy_executor::task]
+async fn ir_task(input: PulseCapture<'static, PIO0>) {
+    tasks::ir_task(
+        input,
+        NecDecoder::new(),
+        &IR_KEYMAP,
+        ChannelSender(CHANNEL.sender()),
+        TimerDelay,
+    )
+    .await
 }
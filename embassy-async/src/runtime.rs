@@ -0,0 +1,210 @@
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use embassy_rp::Peri;
+use embassy_rp::clocks::clk_sys_freq;
+use embassy_rp::gpio::{Input, Pull};
+use embassy_rp::peripherals::USB;
+use embassy_rp::pio::program::pio_asm;
+use embassy_rp::pio::{Common, Config, Direction, FifoJoin, Instance, Pio, PioPin, StateMachine};
+use embassy_rp::usb::Driver;
+use embassy_sync::{
+    blocking_mutex::raw::ThreadModeRawMutex,
+    channel::{Receiver, Sender},
+};
+use embassy_time::{Instant, Timer};
+use embassy_usb::class::cdc_acm::CdcAcmClass;
+use pico_app::console::{Stats, SystemInfo, TaskInfo};
+use pico_app::runtime::{Clock, Delay, EdgeInput, EventReceiver, EventSender, Serial};
+
+pub struct TimerDelay;
+
+impl Delay for TimerDelay {
+    async fn delay_ms(&mut self, millis: u32) {
+        Timer::after_millis(millis as u64).await
+    }
+}
+
+impl Clock for TimerDelay {
+    fn now_ms(&self) -> u32 {
+        Instant::now().as_millis() as u32
+    }
+}
+
+pub struct ButtonInput(pub Input<'static>);
+
+impl pico_app::runtime::Input for ButtonInput {
+    async fn wait_for_low(&mut self) {
+        self.0.wait_for_low().await
+    }
+
+    async fn wait_for_high(&mut self) {
+        self.0.wait_for_high().await
+    }
+
+    fn is_high(&mut self) -> bool {
+        self.0.is_high()
+    }
+}
+
+// IR pulses are as short as 560 µs, reading the clock once the task runs
+// again after an edge would add however long the executor was busy with the
+// other tasks. A PIO state machine counts the length of every low and high
+// phase instead, the edges get their timestamps by adding those up.
+pub struct PulseCapture<'d, P: Instance> {
+    // owns the instruction memory the program was loaded into
+    _common: Common<'d, P>,
+    sm: StateMachine<'d, P, 0>,
+    high: bool,
+    now_us: u32,
+}
+
+impl<'d, P: Instance> PulseCapture<'d, P> {
+    // The pin idles high, like the output of an IR receiver module.
+    pub fn new(pio: Pio<'d, P>, pin: Peri<'d, impl PioPin + 'd>) -> Self {
+        let Pio {
+            mut common,
+            mut sm0,
+            ..
+        } = pio;
+        // Two cycles per count in both loops, `push` stalls rather than
+        // dropping a length so the levels stay in step.
+        let program = pio_asm!(
+            "    wait 1 pin 0",
+            ".wrap_target",
+            "    mov x, ~null",
+            "high:",
+            "    jmp x--, high_test",
+            "high_test:",
+            "    jmp pin, high",
+            "    mov isr, ~x",
+            "    push",
+            "    mov x, ~null",
+            "low:",
+            "    jmp pin, low_end",
+            "    jmp x--, low",
+            "low_end:",
+            "    mov isr, ~x",
+            "    push",
+            ".wrap",
+        );
+        let mut pin = common.make_pio_pin(pin);
+        pin.set_pull(Pull::Up);
+        let mut config = Config::default();
+        config.use_program(&common.load_program(&program.program), &[]);
+        config.set_in_pins(&[&pin]);
+        config.set_jmp_pin(&pin);
+        config.fifo_join = FifoJoin::RxOnly;
+        sm0.set_pin_dirs(Direction::In, &[&pin]);
+        sm0.set_config(&config);
+        sm0.set_enable(true);
+        Self {
+            _common: common,
+            sm: sm0,
+            high: true,
+            now_us: 0,
+        }
+    }
+}
+
+// The timestamps start at the first falling edge rather than at boot, the
+// IR task only looks at the time between edges.
+impl<P: Instance> EdgeInput for PulseCapture<'_, P> {
+    async fn next_edge(&mut self) -> (bool, u32) {
+        let counts = self.sm.rx().wait_pull().await;
+        let cycles = 2 * counts as u64;
+        let duration_us = cycles * 1_000_000 / clk_sys_freq() as u64;
+        self.now_us = self.now_us.wrapping_add(duration_us as u32);
+        self.high = !self.high;
+        (self.high, self.now_us)
+    }
+}
+
+pub struct ChannelSender<T: 'static, const N: usize>(pub Sender<'static, ThreadModeRawMutex, T, N>);
+
+impl<T, const N: usize> EventSender<T> for ChannelSender<T, N> {
+    async fn send(&self, item: T) -> Result<(), T> {
+        self.0.send(item).await;
+        Ok(())
+    }
+}
+
+// Static embassy channels can't be closed, so the receiver never ends.
+pub struct ChannelReceiver<T: 'static, const N: usize>(
+    pub Receiver<'static, ThreadModeRawMutex, T, N>,
+);
+
+impl<T, const N: usize> EventReceiver<T> for ChannelReceiver<T, N> {
+    async fn receive(&mut self) -> Option<T> {
+        Some(self.0.receive().await)
+    }
+}
+
+// Only the thread-mode executor touches these, the RP2040 has no atomic
+// read-modify-write anyway.
+static RX_BYTES: AtomicU32 = AtomicU32::new(0);
+static TX_BYTES: AtomicU32 = AtomicU32::new(0);
+
+fn count(counter: &AtomicU32, bytes: usize) {
+    counter.store(
+        counter.load(Ordering::Relaxed).wrapping_add(bytes as u32),
+        Ordering::Relaxed,
+    );
+}
+
+pub struct UsbSerial<'d>(pub CdcAcmClass<'d, Driver<'d, USB>>);
+
+impl Serial for UsbSerial<'_> {
+    async fn read(&mut self, buffer: &mut [u8]) -> usize {
+        loop {
+            self.0.wait_connection().await;
+            match self.0.read_packet(buffer).await {
+                Ok(len) if len > 0 => {
+                    count(&RX_BYTES, len);
+                    return len;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    async fn write(&mut self, data: &[u8]) {
+        // without a terminal on the other end nobody reads it, don't hold up
+        // the console
+        if !self.0.dtr() {
+            return;
+        }
+        let max_packet_size = self.0.max_packet_size() as usize;
+        for chunk in data.chunks(max_packet_size) {
+            if self.0.write_packet(chunk).await.is_err() {
+                return;
+            }
+            count(&TX_BYTES, chunk.len());
+        }
+        // a full last packet needs a short one to end the transfer
+        if !data.is_empty() && data.len().is_multiple_of(max_packet_size) {
+            self.0.write_packet(&[]).await.ok();
+        }
+    }
+}
+
+// What the console reports on `stats` and `tasks`. The embassy executor
+// doesn't count its idle loops, and USB doesn't drop bytes, the host holds
+// them back instead.
+pub struct Info;
+
+impl SystemInfo for Info {
+    fn stats(&self) -> Stats {
+        Stats {
+            uptime_ms: Instant::now().as_millis() as u32,
+            idle_count: None,
+            rx_bytes: RX_BYTES.load(Ordering::Relaxed),
+            tx_bytes: TX_BYTES.load(Ordering::Relaxed),
+            rx_dropped: 0,
+        }
+    }
+
+    // the embassy executor keeps no statistics per task
+    fn task(&self, _id: usize) -> Option<TaskInfo> {
+        None
+    }
+}
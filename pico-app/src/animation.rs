@@ -0,0 +1,299 @@
+use crate::ButtonDirection;
+use crate::gesture::Gesture;
+use crate::pwm::breathe_level;
+
+pub const FRAME_PERIOD_MS: u32 = 20;
+pub const CROSSFADE_FRAMES: u32 = 25;
+pub const AUTO_CYCLE_FRAMES: u32 = 500;
+
+// A pattern maps a frame tick to the brightness of every LED in the row.
+// Rendering must not depend on anything but `tick`, so frames can be
+// computed (and tested) without any hardware or timer involved.
+pub trait Pattern<const N: usize> {
+    fn render(&self, tick: u32, frame: &mut [u8; N]);
+}
+
+pub struct Scanner {
+    pub frames_per_step: u32,
+    pub fade_per_led: u8,
+}
+
+impl<const N: usize> Pattern<N> for Scanner {
+    fn render(&self, tick: u32, frame: &mut [u8; N]) {
+        let step = (tick / self.frames_per_step.max(1)) as usize;
+        let period = 2 * (N - 1);
+        let position = match period {
+            0 => 0,
+            _ => match step % period {
+                p if p < N => p,
+                p => period - p,
+            },
+        };
+        for (i, level) in frame.iter_mut().enumerate() {
+            let distance = i.abs_diff(position).min(u8::MAX as usize) as u8;
+            *level = u8::MAX.saturating_sub(distance.saturating_mul(self.fade_per_led));
+        }
+    }
+}
+
+pub struct BarGraph {
+    pub level: u8,
+}
+
+impl<const N: usize> Pattern<N> for BarGraph {
+    fn render(&self, _tick: u32, frame: &mut [u8; N]) {
+        // the last LED of the bar shows the remainder as partial brightness
+        let scaled = self.level as usize * N;
+        let full = scaled / u8::MAX as usize;
+        let remainder = (scaled % u8::MAX as usize) as u8;
+        for (i, level) in frame.iter_mut().enumerate() {
+            *level = match i {
+                i if i < full => u8::MAX,
+                i if i == full => remainder,
+                _ => 0,
+            };
+        }
+    }
+}
+
+pub struct Chase {
+    pub spacing: usize,
+    pub frames_per_step: u32,
+}
+
+impl<const N: usize> Pattern<N> for Chase {
+    fn render(&self, tick: u32, frame: &mut [u8; N]) {
+        let spacing = self.spacing.max(1);
+        let offset = (tick / self.frames_per_step.max(1)) as usize % spacing;
+        for (i, level) in frame.iter_mut().enumerate() {
+            *level = if i % spacing == offset { u8::MAX } else { 0 };
+        }
+    }
+}
+
+pub struct Twinkle {
+    pub seed: u32,
+    pub density: u8,
+    pub frames_per_twinkle: u32,
+}
+
+impl<const N: usize> Pattern<N> for Twinkle {
+    fn render(&self, tick: u32, frame: &mut [u8; N]) {
+        let length = self.frames_per_twinkle.max(1);
+        let window = tick / length;
+        let phase = tick % length;
+        for (i, level) in frame.iter_mut().enumerate() {
+            let lit = (hash(self.seed ^ window, i as u32) & 0xff) < self.density as u32;
+            *level = if lit { breathe_level(phase, length) } else { 0 };
+        }
+    }
+}
+
+pub struct Progress {
+    pub percent: u8,
+    pub frames_per_blink: u32,
+}
+
+impl<const N: usize> Pattern<N> for Progress {
+    fn render(&self, tick: u32, frame: &mut [u8; N]) {
+        let done = self.percent.min(100) as usize * N / 100;
+        let head_on = (tick / self.frames_per_blink.max(1)).is_multiple_of(2);
+        for (i, level) in frame.iter_mut().enumerate() {
+            *level = match i {
+                i if i < done => u8::MAX,
+                i if i == done && head_on => u8::MAX,
+                _ => 0,
+            };
+        }
+    }
+}
+
+// Plays `first` for `first_frames`, then `second` for `second_frames`, and
+// starts over. Both patterns see a tick relative to their own start.
+pub struct Sequence<A, B> {
+    pub first: A,
+    pub first_frames: u32,
+    pub second: B,
+    pub second_frames: u32,
+}
+
+impl<A: Pattern<N>, B: Pattern<N>, const N: usize> Pattern<N> for Sequence<A, B> {
+    fn render(&self, tick: u32, frame: &mut [u8; N]) {
+        let tick = tick % (self.first_frames + self.second_frames).max(1);
+        if tick < self.first_frames {
+            self.first.render(tick, frame);
+        } else {
+            self.second.render(tick - self.first_frames, frame);
+        }
+    }
+}
+
+// Blends from one pattern into the other over `frames` ticks, starting at
+// tick `start`.
+pub struct Crossfade<A, B> {
+    pub from: A,
+    pub to: B,
+    pub start: u32,
+    pub frames: u32,
+}
+
+impl<A: Pattern<N>, B: Pattern<N>, const N: usize> Pattern<N> for Crossfade<A, B> {
+    fn render(&self, tick: u32, frame: &mut [u8; N]) {
+        let elapsed = tick.saturating_sub(self.start);
+        self.to.render(tick, frame);
+        if elapsed >= self.frames {
+            return;
+        }
+        let mut from = [0; N];
+        self.from.render(tick, &mut from);
+        for (level, from) in frame.iter_mut().zip(from) {
+            let mixed =
+                (from as u32 * (self.frames - elapsed) + *level as u32 * elapsed) / self.frames;
+            *level = mixed as u8;
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Animation {
+    Scanner,
+    BarGraph,
+    Chase,
+    Twinkle,
+    Progress,
+}
+
+impl Animation {
+    pub const ALL: [Animation; 5] = [
+        Animation::Scanner,
+        Animation::BarGraph,
+        Animation::Chase,
+        Animation::Twinkle,
+        Animation::Progress,
+    ];
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+
+    pub fn previous(self) -> Self {
+        Self::ALL[(self as usize + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
+
+// The built-in animations with their default settings. Bar graph and
+// progress meter sweep through their range so there is something to see.
+impl<const N: usize> Pattern<N> for Animation {
+    fn render(&self, tick: u32, frame: &mut [u8; N]) {
+        match self {
+            Animation::Scanner => Scanner {
+                frames_per_step: 4,
+                fade_per_led: 96,
+            }
+            .render(tick, frame),
+            Animation::BarGraph => BarGraph {
+                level: breathe_level(tick, 100),
+            }
+            .render(tick, frame),
+            Animation::Chase => Chase {
+                spacing: 3,
+                frames_per_step: 8,
+            }
+            .render(tick, frame),
+            Animation::Twinkle => Twinkle {
+                seed: 0x2545_f491,
+                density: 80,
+                frames_per_twinkle: 40,
+            }
+            .render(tick, frame),
+            Animation::Progress => Progress {
+                percent: (tick / 3 % 101) as u8,
+                frames_per_blink: 10,
+            }
+            .render(tick, frame),
+        }
+    }
+}
+
+// Keeps track of the selected animation, crossfades between selections and
+// optionally cycles through all animations on its own.
+pub struct Animator {
+    tick: u32,
+    current: Animation,
+    fading_from: Option<(Animation, u32)>,
+    auto_cycle: bool,
+    selected_at: u32,
+}
+
+impl Animator {
+    pub fn new(animation: Animation) -> Self {
+        Self {
+            tick: 0,
+            current: animation,
+            fading_from: None,
+            auto_cycle: false,
+            selected_at: 0,
+        }
+    }
+
+    pub fn current(&self) -> Animation {
+        self.current
+    }
+
+    pub fn select(&mut self, animation: Animation) {
+        if animation == self.current {
+            return;
+        }
+        info!("ANIMATOR: switching to {}", animation);
+        self.fading_from = Some((self.current, self.tick));
+        self.current = animation;
+        self.selected_at = self.tick;
+    }
+
+    // Single presses step through the animations, a double press toggles
+    // automatic cycling.
+    pub fn handle(&mut self, gesture: Gesture) {
+        match gesture {
+            Gesture::Press(ButtonDirection::Left) => self.select(self.current.previous()),
+            Gesture::Press(ButtonDirection::Right) => self.select(self.current.next()),
+            Gesture::DoublePress(_) => {
+                self.auto_cycle = !self.auto_cycle;
+                self.selected_at = self.tick;
+                info!("ANIMATOR: auto cycle {}", self.auto_cycle);
+            }
+        }
+    }
+
+    pub fn next_frame<const N: usize>(&mut self, frame: &mut [u8; N]) {
+        if self.auto_cycle && self.tick.wrapping_sub(self.selected_at) >= AUTO_CYCLE_FRAMES {
+            self.select(self.current.next());
+        }
+
+        match self.fading_from {
+            Some((from, start)) if self.tick.wrapping_sub(start) < CROSSFADE_FRAMES => Crossfade {
+                from,
+                to: self.current,
+                start,
+                frames: CROSSFADE_FRAMES,
+            }
+            .render(self.tick, frame),
+            _ => {
+                self.fading_from = None;
+                self.current.render(self.tick, frame);
+            }
+        }
+
+        self.tick = self.tick.wrapping_add(1);
+    }
+}
+
+// Small integer hash (xorshift-multiply), good enough to scatter twinkles.
+pub(crate) fn hash(seed: u32, value: u32) -> u32 {
+    let mut x = seed ^ value.wrapping_mul(0x9e37_79b9);
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x85eb_ca6b);
+    x ^= x >> 13;
+    x = x.wrapping_mul(0xc2b2_ae35);
+    x ^ (x >> 16)
+}
@@ -0,0 +1,19 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ButtonDirection {
+    Left,
+    Right,
+}
+
+impl ButtonDirection {
+    // Moves `index` one step in this direction, wrapping around a row of `len` LEDs.
+    pub fn step(self, index: usize, len: usize) -> usize {
+        match self {
+            ButtonDirection::Left => match index {
+                0 => len - 1,
+                _ => index - 1,
+            },
+            ButtonDirection::Right => (index + 1) % len,
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use core::{
+    cell::{Cell, RefCell},
+    future::poll_fn,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+use futures::Stream;
+use heapless::Deque;
+
+use crate::runtime::{EventReceiver, EventSender};
+
+// Items the receiver hasn't taken yet. The embassy firmware sizes its
+// channel the same.
+pub const CHANNEL_SIZE: usize = 4;
+// Senders past the queue's capacity keep retrying until they find room.
+pub const MAX_WAITING_SENDERS: usize = 4;
+
+// Queues up to `N` items in order. Once the queue is full, senders wait
+// until the receiver has taken an item, so nothing gets lost. The channel
+// closes when the receiver closes it or goes away, or when the last sender
+// goes away. A receiver that runs before the first `get_sender` waits for it.
+pub struct Channel<T, const N: usize = CHANNEL_SIZE> {
+    items: RefCell<Deque<T, N>>,
+    receiver_waker: RefCell<Option<Waker>>,
+    sender_wakers: RefCell<Deque<Waker, MAX_WAITING_SENDERS>>,
+    senders: Cell<usize>,
+    // no more items get in, the receiver still takes the queued ones
+    closed: Cell<bool>,
+}
+
+impl<T, const N: usize> Channel<T, N> {
+    pub const fn new() -> Self {
+        Self {
+            items: RefCell::new(Deque::new()),
+            receiver_waker: RefCell::new(None),
+            sender_wakers: RefCell::new(Deque::new()),
+            senders: Cell::new(0),
+            closed: Cell::new(false),
+        }
+    }
+
+    pub fn get_sender(&self) -> Sender<'_, T, N> {
+        self.senders.set(self.senders.get() + 1);
+        Sender { channel: self }
+    }
+
+    // There is one receiver per channel, dropping it closes the channel.
+    pub fn get_receiver(&self) -> Receiver<'_, T, N> {
+        Receiver { channel: self }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.borrow().is_empty()
+    }
+
+    fn poll_send(&self, item: &mut Option<T>, cx: &mut Context<'_>) -> Poll<Result<(), T>> {
+        let value = item.take().expect("send polled after completion");
+        if self.closed.get() {
+            return Poll::Ready(Err(value));
+        }
+        let pushed = self.items.borrow_mut().push_back(value);
+        match pushed {
+            Ok(()) => {
+                self.wake_receiver();
+                Poll::Ready(Ok(()))
+            }
+            Err(value) => {
+                *item = Some(value);
+                self.wait_for_room(cx.waker());
+                Poll::Pending
+            }
+        }
+    }
+
+    fn wait_for_room(&self, waker: &Waker) {
+        let mut wakers = self.sender_wakers.borrow_mut();
+        if wakers.iter().any(|waiting| waiting.will_wake(waker)) {
+            return;
+        }
+        if wakers.push_back(waker.clone()).is_err() {
+            waker.wake_by_ref();
+        }
+    }
+
+    fn close(&self) {
+        self.closed.set(true);
+        self.wake_senders();
+    }
+
+    fn wake_receiver(&self) {
+        if let Some(waker) = self.receiver_waker.borrow().as_ref() {
+            waker.wake_by_ref();
+        }
+    }
+
+    // There is room again (or never will be): every waiting sender retries.
+    fn wake_senders(&self) {
+        loop {
+            let waker = self.sender_wakers.borrow_mut().pop_front();
+            match waker {
+                Some(waker) => waker.wake(),
+                None => break,
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> Default for Channel<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Sender<'a, T, const N: usize = CHANNEL_SIZE> {
+    channel: &'a Channel<T, N>,
+}
+
+impl<T, const N: usize> Sender<'_, T, N> {
+    // Waits while the queue is full, hands the item back once the receiver
+    // is gone.
+    pub async fn send(&self, item: T) -> Result<(), T> {
+        let mut item = Some(item);
+        poll_fn(|cx| self.channel.poll_send(&mut item, cx)).await
+    }
+}
+
+impl<T, const N: usize> EventSender<T> for Sender<'_, T, N> {
+    async fn send(&self, item: T) -> Result<(), T> {
+        Sender::send(self, item).await
+    }
+}
+
+impl<T, const N: usize> Clone for Sender<'_, T, N> {
+    fn clone(&self) -> Self {
+        self.channel.get_sender()
+    }
+}
+
+impl<T, const N: usize> Drop for Sender<'_, T, N> {
+    fn drop(&mut self) {
+        let senders = self.channel.senders.get() - 1;
+        self.channel.senders.set(senders);
+        if senders == 0 {
+            self.channel.closed.set(true);
+            self.channel.wake_receiver();
+        }
+    }
+}
+
+pub struct Receiver<'a, T, const N: usize = CHANNEL_SIZE> {
+    channel: &'a Channel<T, N>,
+}
+
+impl<T, const N: usize> Receiver<'_, T, N> {
+    // Resolves to `None` once the channel is closed and the queue is empty.
+    pub async fn receive(&mut self) -> Option<T> {
+        poll_fn(|cx| self.poll_receive(cx)).await
+    }
+
+    // Refuses further items, waiting senders get theirs back. What is
+    // queued already can still be received.
+    pub fn close(&mut self) {
+        self.channel.close();
+    }
+
+    fn poll_receive(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let item = self.channel.items.borrow_mut().pop_front();
+        match item {
+            Some(item) => {
+                self.channel.wake_senders();
+                Poll::Ready(Some(item))
+            }
+            None if self.channel.closed.get() => Poll::Ready(None),
+            None => {
+                self.channel
+                    .receiver_waker
+                    .replace(Some(cx.waker().clone()));
+                Poll::Pending
+            }
+        }
+    }
+}
+
+// Tells the senders, waiting ones included, that nobody listens anymore.
+impl<T, const N: usize> Drop for Receiver<'_, T, N> {
+    fn drop(&mut self) {
+        self.channel.receiver_waker.replace(None);
+        self.channel.close();
+    }
+}
+
+// Ends where `receive` resolves to `None`.
+impl<T, const N: usize> Stream for Receiver<'_, T, N> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.poll_receive(cx)
+    }
+}
+
+impl<T, const N: usize> EventReceiver<T> for Receiver<'_, T, N> {
+    async fn receive(&mut self) -> Option<T> {
+        Receiver::receive(self).await
+    }
+}
@@ -0,0 +1,298 @@
+use core::fmt::{self, Write};
+use core::str::SplitWhitespace;
+
+use crate::led::{LedControl, QueryError};
+
+pub const CONSOLE_LINE_LEN: usize = 64;
+pub const PROMPT: &str = "> ";
+
+pub const MIN_BLINK_MS: u32 = 10;
+pub const MAX_BLINK_MS: u32 = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Command {
+    Help,
+    // ask the LED task which LED is active
+    ActiveLed,
+    // select one LED of the row
+    Led(usize),
+    // blink period in milliseconds
+    Blink(u32),
+    Stats,
+    Tasks,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ParseError {
+    Empty,
+    UnknownCommand,
+    MissingArgument,
+    InvalidArgument,
+    TooManyArguments,
+}
+
+impl ParseError {
+    fn message(self) -> &'static str {
+        match self {
+            ParseError::Empty => "empty line",
+            ParseError::UnknownCommand => "unknown command, try `help`",
+            ParseError::MissingArgument => "missing argument",
+            ParseError::InvalidArgument => "invalid argument",
+            ParseError::TooManyArguments => "too many arguments",
+        }
+    }
+}
+
+fn argument<T: core::str::FromStr>(words: &mut SplitWhitespace) -> Result<T, ParseError> {
+    let word = words.next().ok_or(ParseError::MissingArgument)?;
+    word.parse().map_err(|_| ParseError::InvalidArgument)
+}
+
+// Parses one line: a command name and its arguments, separated by any
+// amount of whitespace.
+pub fn parse(line: &str) -> Result<Command, ParseError> {
+    let mut words = line.split_whitespace();
+    let command = match words.next().ok_or(ParseError::Empty)? {
+        "help" | "?" => Command::Help,
+        "led" => match words.next() {
+            None => Command::ActiveLed,
+            Some(word) => Command::Led(word.parse().map_err(|_| ParseError::InvalidArgument)?),
+        },
+        "blink" => {
+            let period_ms = argument(&mut words)?;
+            if !(MIN_BLINK_MS..=MAX_BLINK_MS).contains(&period_ms) {
+                return Err(ParseError::InvalidArgument);
+            }
+            Command::Blink(period_ms)
+        }
+        "stats" => Command::Stats,
+        "tasks" => Command::Tasks,
+        _ => return Err(ParseError::UnknownCommand),
+    };
+    match words.next() {
+        Some(_) => Err(ParseError::TooManyArguments),
+        None => Ok(command),
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub uptime_ms: u32,
+    // how often the executor found nothing to do and went to sleep, `None`
+    // if it doesn't count that
+    pub idle_count: Option<u32>,
+    pub rx_bytes: u32,
+    pub tx_bytes: u32,
+    // received bytes lost because the buffer was full
+    pub rx_dropped: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TaskInfo {
+    pub polls: u32,
+    pub finished: bool,
+}
+
+// What the firmware reports about itself on `stats` and `tasks`.
+pub trait SystemInfo {
+    fn stats(&self) -> Stats;
+    // `None` past the last task
+    fn task(&self, id: usize) -> Option<TaskInfo>;
+}
+
+// Runs a parsed command and writes the reply, one `\r\n` terminated line at
+// a time.
+pub async fn execute<W: Write, I: SystemInfo>(
+    command: Command,
+    control: &LedControl<'_>,
+    info: &I,
+    out: &mut W,
+) -> fmt::Result {
+    match command {
+        Command::Help => {
+            write!(
+                out,
+                "led [N]   show the active LED or select LED N, 0 to {}\r\n",
+                control.len() - 1
+            )?;
+            write!(
+                out,
+                "blink MS  blink period, {}..{} ms\r\n",
+                MIN_BLINK_MS, MAX_BLINK_MS
+            )?;
+            write!(out, "stats     uptime and I/O counters\r\n")?;
+            write!(out, "tasks     executor tasks\r\n")
+        }
+        Command::ActiveLed => match control.active_led().await {
+            Ok(led) => write!(out, "led {}\r\n", led),
+            Err(QueryError::NoLedTask) => write!(out, "error: no LED task in this mode\r\n"),
+            Err(QueryError::Busy) => write!(out, "error: busy, try again\r\n"),
+            Err(QueryError::NoAnswer) => write!(out, "error: the LED task doesn't answer\r\n"),
+        },
+        // nothing would pick the change up
+        Command::Led(_) | Command::Blink(_) if !control.is_attached() => {
+            write!(out, "error: no LED task in this mode\r\n")
+        }
+        Command::Led(led) if led >= control.len() => {
+            write!(
+                out,
+                "error: no LED {}, there are {}\r\n",
+                led,
+                control.len()
+            )
+        }
+        Command::Led(led) => {
+            control.select(led);
+            write!(out, "ok\r\n")
+        }
+        Command::Blink(period_ms) => {
+            control.set_blink_ms(period_ms);
+            write!(out, "ok\r\n")
+        }
+        Command::Stats => {
+            let stats = info.stats();
+            write!(out, "uptime   {} ms\r\n", stats.uptime_ms)?;
+            if let Some(idle_count) = stats.idle_count {
+                write!(out, "idle     {}\r\n", idle_count)?;
+            }
+            write!(out, "rx       {} bytes\r\n", stats.rx_bytes)?;
+            write!(out, "tx       {} bytes\r\n", stats.tx_bytes)?;
+            write!(out, "dropped  {} bytes\r\n", stats.rx_dropped)
+        }
+        Command::Tasks if info.task(0).is_none() => {
+            write!(out, "(this firmware keeps no task statistics)\r\n")
+        }
+        Command::Tasks => {
+            write!(out, "id  polls       state\r\n")?;
+            for id in 0.. {
+                let Some(task) = info.task(id) else {
+                    break;
+                };
+                let state = if task.finished { "finished" } else { "running" };
+                write!(out, "{:<3} {:<11} {}\r\n", id, task.polls, state)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+// Parses and runs one line, errors included in the reply.
+pub async fn run_line<W: Write, I: SystemInfo>(
+    line: &str,
+    control: &LedControl<'_>,
+    info: &I,
+    out: &mut W,
+) -> fmt::Result {
+    match parse(line) {
+        Ok(command) => {
+            debug!("CONSOLE: {}", command);
+            execute(command, control, info, out).await
+        }
+        Err(ParseError::Empty) => Ok(()),
+        Err(error) => write!(out, "error: {}\r\n", error.message()),
+    }
+}
+
+// What a received byte did to the line being edited, tells the console what
+// to send back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineEvent {
+    Ignored,
+    Echo(u8),
+    Erase,
+    // the line is complete, see `LineBuffer::as_str`
+    Done,
+}
+
+// A line editor for dumb terminals: printable ASCII is collected, backspace
+// removes the last character and CR, LF or CR LF ends the line. Characters
+// past the capacity are dropped.
+pub struct LineBuffer<const N: usize> {
+    buffer: [u8; N],
+    len: usize,
+    after_cr: bool,
+}
+
+impl<const N: usize> LineBuffer<N> {
+    pub const fn new() -> Self {
+        Self {
+            buffer: [0; N],
+            len: 0,
+            after_cr: false,
+        }
+    }
+
+    pub fn push(&mut self, byte: u8) -> LineEvent {
+        let after_cr = core::mem::replace(&mut self.after_cr, byte == b'\r');
+        match byte {
+            b'\n' if after_cr => LineEvent::Ignored,
+            b'\r' | b'\n' => LineEvent::Done,
+            0x08 | 0x7f if self.len > 0 => {
+                self.len -= 1;
+                LineEvent::Erase
+            }
+            b' '..=b'~' if self.len < N => {
+                self.buffer[self.len] = byte;
+                self.len += 1;
+                LineEvent::Echo(byte)
+            }
+            _ => LineEvent::Ignored,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        // only printable ASCII gets in
+        core::str::from_utf8(&self.buffer[..self.len]).unwrap_or("")
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl<const N: usize> Default for LineBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Collects a reply before it goes out in one write. Text that doesn't fit
+// is cut off.
+pub struct Reply<const N: usize> {
+    buffer: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Reply<N> {
+    pub const fn new() -> Self {
+        Self {
+            buffer: [0; N],
+            len: 0,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer[..self.len]
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl<const N: usize> Default for Reply<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Write for Reply<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let n = s.len().min(N - self.len);
+        self.buffer[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+        if n < s.len() { Err(fmt::Error) } else { Ok(()) }
+    }
+}
@@ -0,0 +1,146 @@
+use crate::button::ButtonDirection;
+
+// Quadrature decoding by table lookup: the index is the previous and the
+// current A/B state (A is the higher bit), the entry the step taken. Moves
+// that skip a state are bounces or missed edges and count as no movement.
+// Positive steps turn right (00 → 10 → 11 → 01 → 00); swap A and B if the
+// encoder counts the wrong way round.
+const TRANSITIONS: [i8; 16] = [0, -1, 1, 0, 1, 0, 0, -1, -1, 0, 0, 1, 0, 1, -1, 0];
+
+// How many quadrature steps an encoder makes from one click to the next.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Detent {
+    // one click per full cycle, resting with both contacts open (pulled high)
+    Full,
+    // two clicks per cycle, resting whenever A and B are equal
+    Half,
+    // every step is a click
+    Quarter,
+}
+
+impl Detent {
+    fn steps(self) -> i8 {
+        match self {
+            Detent::Full => 4,
+            Detent::Half => 2,
+            Detent::Quarter => 1,
+        }
+    }
+
+    fn is_rest(self, state: u8) -> bool {
+        match self {
+            Detent::Full => state == 0b11,
+            Detent::Half => state == 0b00 || state == 0b11,
+            Detent::Quarter => true,
+        }
+    }
+}
+
+fn state(a: bool, b: bool) -> u8 {
+    ((a as u8) << 1) | b as u8
+}
+
+// Turns A/B samples into one step per detent. Steps are only reported once
+// the encoder settles in a rest position, so contact bounce in between
+// cancels out.
+pub struct QuadratureDecoder {
+    detent: Detent,
+    state: u8,
+    count: i8,
+}
+
+impl QuadratureDecoder {
+    pub fn new(detent: Detent, a: bool, b: bool) -> Self {
+        Self {
+            detent,
+            state: state(a, b),
+            count: 0,
+        }
+    }
+
+    pub fn update(&mut self, a: bool, b: bool) -> Option<ButtonDirection> {
+        let next = state(a, b);
+        self.count += TRANSITIONS[((self.state << 2) | next) as usize];
+        self.state = next;
+        if !self.detent.is_rest(next) {
+            return None;
+        }
+
+        // more than half way into the next detent counts as a step
+        let half = (self.detent.steps() + 1) / 2;
+        let count = core::mem::take(&mut self.count);
+        match count {
+            c if c >= half => Some(ButtonDirection::Right),
+            c if c <= -half => Some(ButtonDirection::Left),
+            _ => None,
+        }
+    }
+}
+
+// Fast turning moves further: detents closer together than `slow_ms` count
+// more than once, up to `max_steps` at `fast_ms` or less.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Acceleration {
+    pub slow_ms: u32,
+    pub fast_ms: u32,
+    pub max_steps: u32,
+}
+
+impl Acceleration {
+    pub const NONE: Acceleration = Acceleration {
+        slow_ms: 0,
+        fast_ms: 0,
+        max_steps: 1,
+    };
+
+    pub fn steps(&self, interval_ms: u32) -> u32 {
+        if interval_ms >= self.slow_ms || self.max_steps <= 1 {
+            return 1;
+        }
+        let range = self.slow_ms.saturating_sub(self.fast_ms).max(1);
+        let speed = (self.slow_ms - interval_ms).min(range);
+        1 + (self.max_steps - 1) * speed / range
+    }
+}
+
+impl Default for Acceleration {
+    fn default() -> Self {
+        Self {
+            slow_ms: 100,
+            fast_ms: 20,
+            max_steps: 5,
+        }
+    }
+}
+
+// Decoder plus acceleration: every detent yields a direction and how many
+// steps it is worth. Turning back always starts slow.
+pub struct Encoder {
+    decoder: QuadratureDecoder,
+    acceleration: Acceleration,
+    last: Option<(ButtonDirection, u32)>,
+}
+
+impl Encoder {
+    pub fn new(decoder: QuadratureDecoder, acceleration: Acceleration) -> Self {
+        Self {
+            decoder,
+            acceleration,
+            last: None,
+        }
+    }
+
+    pub fn update(&mut self, a: bool, b: bool, now_ms: u32) -> Option<(ButtonDirection, u32)> {
+        let direction = self.decoder.update(a, b)?;
+        let steps = match self.last {
+            Some((last, last_ms)) if last == direction => {
+                self.acceleration.steps(now_ms.wrapping_sub(last_ms))
+            }
+            _ => 1,
+        };
+        self.last = Some((direction, now_ms));
+        debug!("ENCODER: {} x{}", direction, steps);
+        Some((direction, steps))
+    }
+}
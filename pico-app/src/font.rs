@@ -0,0 +1,85 @@
+// 5×5 bitmap font in the spirit of the micro:bit one. Every glyph is five
+// rows of five bits, the most significant of the five bits is the leftmost
+// column. Lower case letters are shown as upper case ones.
+
+pub const GLYPH_HEIGHT: usize = 5;
+pub const GLYPH_SPACING: usize = 1;
+const SPACE_WIDTH: usize = 3;
+
+pub type Glyph = [u8; GLYPH_HEIGHT];
+
+const UNKNOWN: Glyph = [0b11111, 0b10001, 0b10001, 0b10001, 0b11111];
+
+const LETTERS: [Glyph; 26] = [
+    [0b01100, 0b10010, 0b11110, 0b10010, 0b10010], // A
+    [0b11100, 0b10010, 0b11100, 0b10010, 0b11100], // B
+    [0b01110, 0b10000, 0b10000, 0b10000, 0b01110], // C
+    [0b11100, 0b10010, 0b10010, 0b10010, 0b11100], // D
+    [0b11110, 0b10000, 0b11100, 0b10000, 0b11110], // E
+    [0b11110, 0b10000, 0b11100, 0b10000, 0b10000], // F
+    [0b01110, 0b10000, 0b10011, 0b10001, 0b01110], // G
+    [0b10010, 0b10010, 0b11110, 0b10010, 0b10010], // H
+    [0b11100, 0b01000, 0b01000, 0b01000, 0b11100], // I
+    [0b11111, 0b00010, 0b00010, 0b10010, 0b01100], // J
+    [0b10010, 0b10100, 0b11000, 0b10100, 0b10010], // K
+    [0b10000, 0b10000, 0b10000, 0b10000, 0b11110], // L
+    [0b10001, 0b11011, 0b10101, 0b10001, 0b10001], // M
+    [0b10001, 0b11001, 0b10101, 0b10011, 0b10001], // N
+    [0b01100, 0b10010, 0b10010, 0b10010, 0b01100], // O
+    [0b11100, 0b10010, 0b11100, 0b10000, 0b10000], // P
+    [0b01100, 0b10010, 0b10010, 0b01100, 0b00110], // Q
+    [0b11100, 0b10010, 0b11100, 0b10010, 0b10001], // R
+    [0b01110, 0b10000, 0b01100, 0b00010, 0b11100], // S
+    [0b11111, 0b00100, 0b00100, 0b00100, 0b00100], // T
+    [0b10010, 0b10010, 0b10010, 0b10010, 0b01100], // U
+    [0b10001, 0b10001, 0b10001, 0b01010, 0b00100], // V
+    [0b10001, 0b10001, 0b10101, 0b11011, 0b10001], // W
+    [0b10010, 0b10010, 0b01100, 0b10010, 0b10010], // X
+    [0b10001, 0b01010, 0b00100, 0b00100, 0b00100], // Y
+    [0b11110, 0b00100, 0b01000, 0b10000, 0b11110], // Z
+];
+
+const DIGITS: [Glyph; 10] = [
+    [0b01100, 0b10110, 0b10010, 0b11010, 0b01100], // 0
+    [0b01000, 0b11000, 0b01000, 0b01000, 0b11100], // 1
+    [0b11100, 0b00010, 0b01100, 0b10000, 0b11110], // 2
+    [0b11110, 0b00010, 0b00100, 0b10010, 0b01100], // 3
+    [0b00110, 0b01010, 0b10010, 0b11111, 0b00010], // 4
+    [0b11111, 0b10000, 0b11110, 0b00001, 0b11110], // 5
+    [0b00010, 0b00100, 0b01110, 0b10001, 0b01110], // 6
+    [0b11111, 0b00010, 0b00100, 0b01000, 0b10000], // 7
+    [0b01110, 0b10001, 0b01110, 0b10001, 0b01110], // 8
+    [0b01110, 0b10001, 0b01110, 0b00100, 0b01000], // 9
+];
+
+pub fn glyph(c: char) -> Glyph {
+    match c {
+        'A'..='Z' => LETTERS[c as usize - 'A' as usize],
+        'a'..='z' => LETTERS[c as usize - 'a' as usize],
+        '0'..='9' => DIGITS[c as usize - '0' as usize],
+        ' ' => [0; GLYPH_HEIGHT],
+        '!' => [0b10000, 0b10000, 0b10000, 0b00000, 0b10000],
+        '?' => [0b01110, 0b10001, 0b00110, 0b00000, 0b00100],
+        '.' => [0b00000, 0b00000, 0b00000, 0b00000, 0b10000],
+        ',' => [0b00000, 0b00000, 0b00000, 0b01000, 0b10000],
+        ':' => [0b00000, 0b10000, 0b00000, 0b10000, 0b00000],
+        '\'' => [0b10000, 0b10000, 0b00000, 0b00000, 0b00000],
+        '-' => [0b00000, 0b00000, 0b11100, 0b00000, 0b00000],
+        '+' => [0b00000, 0b01000, 0b11100, 0b01000, 0b00000],
+        _ => UNKNOWN,
+    }
+}
+
+// Glyphs are drawn left aligned; the width is up to the rightmost lit column.
+pub fn glyph_width(c: char) -> usize {
+    let columns = glyph(c).iter().fold(0, |columns, row| columns | row);
+    match columns {
+        0 => SPACE_WIDTH,
+        _ => 5 - columns.trailing_zeros() as usize,
+    }
+}
+
+// Whether the pixel in column `x`, row `y` of the glyph is lit.
+pub fn glyph_pixel(c: char, x: usize, y: usize) -> bool {
+    x < 5 && y < GLYPH_HEIGHT && glyph(c)[y] & (0b10000 >> x) != 0
+}
@@ -0,0 +1,127 @@
+// Anything that can show the pixels of a frame buffer, e.g. a row of LEDs.
+pub trait Display<const W: usize, const H: usize> {
+    fn write_pixel(&mut self, x: usize, y: usize, level: u8);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Blend {
+    // the brighter of both pixels wins
+    Lighten,
+    // pixels add up, saturating at full brightness
+    Add,
+    // the layer scales the pixels below it
+    Mask,
+    // every lit pixel of the layer replaces the one below it
+    Over,
+}
+
+// Brightness of every pixel of a `W`×`H` display. A single LED row is a
+// `FrameBuffer<N>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameBuffer<const W: usize, const H: usize = 1> {
+    pixels: [[u8; W]; H],
+}
+
+impl<const W: usize, const H: usize> FrameBuffer<W, H> {
+    pub const fn new() -> Self {
+        Self {
+            pixels: [[0; W]; H],
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y][x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, level: u8) {
+        self.pixels[y][x] = level;
+    }
+
+    pub fn fill(&mut self, level: u8) {
+        self.pixels = [[level; W]; H];
+    }
+
+    pub fn clear(&mut self) {
+        self.fill(0);
+    }
+
+    pub fn row(&self, y: usize) -> &[u8; W] {
+        &self.pixels[y]
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> &mut [u8; W] {
+        &mut self.pixels[y]
+    }
+
+    // Coordinates and new level of every pixel that differs from `previous`.
+    pub fn diff<'a>(
+        &'a self,
+        previous: &'a FrameBuffer<W, H>,
+    ) -> impl Iterator<Item = (usize, usize, u8)> + 'a {
+        (0..H).flat_map(move |y| {
+            (0..W).filter_map(move |x| {
+                let level = self.pixels[y][x];
+                (level != previous.pixels[y][x]).then_some((x, y, level))
+            })
+        })
+    }
+
+    pub fn compose(&mut self, layer: &FrameBuffer<W, H>, blend: Blend) {
+        for (row, layer_row) in self.pixels.iter_mut().zip(layer.pixels.iter()) {
+            for (pixel, &layer_pixel) in row.iter_mut().zip(layer_row.iter()) {
+                *pixel = match blend {
+                    Blend::Lighten => (*pixel).max(layer_pixel),
+                    Blend::Add => pixel.saturating_add(layer_pixel),
+                    Blend::Mask => (*pixel as u16 * layer_pixel as u16 / u8::MAX as u16) as u8,
+                    Blend::Over if layer_pixel > 0 => layer_pixel,
+                    Blend::Over => *pixel,
+                };
+            }
+        }
+    }
+}
+
+impl<const W: usize, const H: usize> Default for FrameBuffer<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Remembers what the display currently shows so that a refresh only writes
+// the pixels that changed.
+pub struct Refresher<const W: usize, const H: usize = 1> {
+    shown: FrameBuffer<W, H>,
+    synced: bool,
+}
+
+impl<const W: usize, const H: usize> Refresher<W, H> {
+    pub const fn new() -> Self {
+        Self {
+            shown: FrameBuffer::new(),
+            synced: false,
+        }
+    }
+
+    pub fn refresh<D: Display<W, H>>(&mut self, frame: &FrameBuffer<W, H>, display: &mut D) {
+        if !self.synced {
+            // the initial state of the display is unknown, write everything
+            for y in 0..H {
+                for x in 0..W {
+                    display.write_pixel(x, y, frame.get(x, y));
+                }
+            }
+            self.synced = true;
+        } else {
+            for (x, y, level) in frame.diff(&self.shown) {
+                display.write_pixel(x, y, level);
+            }
+        }
+        self.shown = *frame;
+    }
+}
+
+impl<const W: usize, const H: usize> Default for Refresher<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,271 @@
+use crate::ButtonDirection;
+use crate::animation::hash;
+use crate::pwm::breathe_level;
+
+pub const PONG_START_STEP_MS: u32 = 300;
+pub const PONG_MIN_STEP_MS: u32 = 60;
+pub const PONG_SPEEDUP_MS: u32 = 20;
+pub const PONG_WINNING_SCORE: u8 = 5;
+
+pub const REACTION_MIN_WAIT_MS: u32 = 1000;
+pub const REACTION_MAX_WAIT_MS: u32 = 4000;
+// the last result is shown as a bar with one LED per step
+pub const REACTION_MS_PER_LED: u32 = 50;
+
+const BLINK_MS: u32 = 500;
+
+// A game played with the two buttons on an LED row. Like a `Pattern`, a game
+// only sees the timestamps it is given, so it can be driven by scripted
+// inputs on the host.
+pub trait Game<const N: usize> {
+    fn press(&mut self, side: ButtonDirection, now_ms: u32);
+    fn update(&mut self, now_ms: u32);
+    fn render(&self, now_ms: u32, frame: &mut [u8; N]);
+}
+
+// Whether `now` has reached `deadline`, robust against the clock wrapping.
+fn reached(now_ms: u32, deadline_ms: u32) -> bool {
+    now_ms.wrapping_sub(deadline_ms) as i32 >= 0
+}
+
+fn end_of<const N: usize>(side: ButtonDirection) -> usize {
+    match side {
+        ButtonDirection::Left => 0,
+        ButtonDirection::Right => N - 1,
+    }
+}
+
+fn opponent(side: ButtonDirection) -> ButtonDirection {
+    match side {
+        ButtonDirection::Left => ButtonDirection::Right,
+        ButtonDirection::Right => ButtonDirection::Left,
+    }
+}
+
+// One-dimensional Pong: the ball travels along the row and the player at the
+// end it is heading to has to press their button while the ball is on the
+// last LED. Every return makes the ball faster, a miss scores a point for
+// the other side and the player who missed serves next.
+pub struct Pong<const N: usize> {
+    ball: usize,
+    heading: ButtonDirection,
+    step_ms: u32,
+    // `None` while the ball waits for the serve
+    next_step_ms: Option<u32>,
+    score: [u8; 2],
+    server: ButtonDirection,
+}
+
+impl<const N: usize> Pong<N> {
+    pub fn new() -> Self {
+        const { assert!(N > 1, "Pong needs at least two LEDs") };
+        Self {
+            ball: 0,
+            heading: ButtonDirection::Right,
+            step_ms: PONG_START_STEP_MS,
+            next_step_ms: None,
+            score: [0; 2],
+            server: ButtonDirection::Left,
+        }
+    }
+
+    pub fn ball(&self) -> usize {
+        self.ball
+    }
+
+    pub fn is_serving(&self) -> bool {
+        self.next_step_ms.is_none()
+    }
+
+    pub fn server(&self) -> ButtonDirection {
+        self.server
+    }
+
+    // Points of the left and the right player.
+    pub fn score(&self) -> (u8, u8) {
+        (self.score[0], self.score[1])
+    }
+
+    pub fn step_ms(&self) -> u32 {
+        self.step_ms
+    }
+
+    fn serve_from(&mut self, side: ButtonDirection) {
+        self.server = side;
+        self.ball = end_of::<N>(side);
+        self.heading = opponent(side);
+        self.step_ms = PONG_START_STEP_MS;
+        self.next_step_ms = None;
+    }
+
+    fn miss(&mut self) {
+        let winner = opponent(self.heading);
+        self.score[winner as usize] += 1;
+        info!(
+            "PONG: point for {}, {} : {}",
+            winner, self.score[0], self.score[1]
+        );
+        if self.score[winner as usize] >= PONG_WINNING_SCORE {
+            info!("PONG: {} wins", winner);
+            self.score = [0; 2];
+        }
+        self.serve_from(self.heading);
+    }
+}
+
+impl<const N: usize> Default for Pong<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Game<N> for Pong<N> {
+    fn press(&mut self, side: ButtonDirection, now_ms: u32) {
+        match self.next_step_ms {
+            None if side == self.server => {
+                debug!("PONG: {} serves", side);
+                self.next_step_ms = Some(now_ms.wrapping_add(self.step_ms));
+            }
+            Some(_) if side == self.heading && self.ball == end_of::<N>(side) => {
+                self.heading = opponent(side);
+                self.step_ms = self
+                    .step_ms
+                    .saturating_sub(PONG_SPEEDUP_MS)
+                    .max(PONG_MIN_STEP_MS);
+                debug!("PONG: {} returns, step {} ms", side, self.step_ms);
+                self.next_step_ms = Some(now_ms.wrapping_add(self.step_ms));
+            }
+            // too early, or not this player's turn
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, now_ms: u32) {
+        while let Some(next_step_ms) = self.next_step_ms
+            && reached(now_ms, next_step_ms)
+        {
+            if self.ball == end_of::<N>(self.heading) {
+                self.miss();
+                return;
+            }
+            self.ball = self.heading.step(self.ball, N);
+            self.next_step_ms = Some(next_step_ms.wrapping_add(self.step_ms));
+        }
+    }
+
+    fn render(&self, now_ms: u32, frame: &mut [u8; N]) {
+        *frame = [0; N];
+        frame[self.ball] = match self.next_step_ms {
+            // the ball pulses while it waits for the serve
+            None => breathe_level(now_ms, 2 * BLINK_MS),
+            Some(_) => u8::MAX,
+        };
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ReactionState {
+    Idle,
+    Waiting { go_ms: u32 },
+    Go { since_ms: u32 },
+}
+
+// Press to arm, wait for the whole row to light up after a random delay, then
+// press again as fast as possible. Pressing before the row lights up is a
+// false start.
+pub struct ReactionTimer {
+    state: ReactionState,
+    last_ms: Option<u32>,
+    best_ms: Option<u32>,
+    seed: u32,
+}
+
+impl ReactionTimer {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            state: ReactionState::Idle,
+            last_ms: None,
+            best_ms: None,
+            seed,
+        }
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        matches!(self.state, ReactionState::Waiting { .. })
+    }
+
+    pub fn is_go(&self) -> bool {
+        matches!(self.state, ReactionState::Go { .. })
+    }
+
+    // Reaction time of the last round, `None` after a false start.
+    pub fn last_ms(&self) -> Option<u32> {
+        self.last_ms
+    }
+
+    pub fn best_ms(&self) -> Option<u32> {
+        self.best_ms
+    }
+
+    fn wait_ms(&self, now_ms: u32) -> u32 {
+        let range = REACTION_MAX_WAIT_MS - REACTION_MIN_WAIT_MS;
+        REACTION_MIN_WAIT_MS + hash(self.seed, now_ms) % range
+    }
+}
+
+impl<const N: usize> Game<N> for ReactionTimer {
+    fn press(&mut self, _side: ButtonDirection, now_ms: u32) {
+        self.state = match self.state {
+            ReactionState::Idle => {
+                debug!("REACTION: armed");
+                ReactionState::Waiting {
+                    go_ms: now_ms.wrapping_add(self.wait_ms(now_ms)),
+                }
+            }
+            ReactionState::Waiting { .. } => {
+                info!("REACTION: false start");
+                self.last_ms = None;
+                ReactionState::Idle
+            }
+            ReactionState::Go { since_ms } => {
+                let reaction_ms = now_ms.wrapping_sub(since_ms);
+                info!("REACTION: {=u32} ms", reaction_ms);
+                self.last_ms = Some(reaction_ms);
+                if self.best_ms.is_none_or(|best| reaction_ms < best) {
+                    info!("REACTION: new best time");
+                    self.best_ms = Some(reaction_ms);
+                }
+                ReactionState::Idle
+            }
+        };
+    }
+
+    fn update(&mut self, now_ms: u32) {
+        if let ReactionState::Waiting { go_ms } = self.state
+            && reached(now_ms, go_ms)
+        {
+            // measure from when the LEDs were due, not from when we noticed
+            self.state = ReactionState::Go { since_ms: go_ms };
+        }
+    }
+
+    fn render(&self, now_ms: u32, frame: &mut [u8; N]) {
+        *frame = match self.state {
+            ReactionState::Go { .. } => [u8::MAX; N],
+            ReactionState::Waiting { .. } => [0; N],
+            ReactionState::Idle => {
+                let mut frame = [0; N];
+                match self.last_ms {
+                    Some(ms) => {
+                        let lit = (ms / REACTION_MS_PER_LED + 1).min(N as u32) as usize;
+                        frame[..lit].fill(u8::MAX);
+                    }
+                    // nothing to show yet or false start: blink the first LED
+                    None if (now_ms / BLINK_MS).is_multiple_of(2) => frame[0] = u8::MAX,
+                    None => {}
+                }
+                frame
+            }
+        };
+    }
+}
@@ -0,0 +1,57 @@
+use crate::ButtonDirection;
+
+pub const DOUBLE_PRESS_WINDOW_MS: u32 = 300;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Gesture {
+    Press(ButtonDirection),
+    DoublePress(ButtonDirection),
+}
+
+// Turns raw button presses into gestures. A single press is only reported
+// once the double press window has passed without a second press, so
+// `poll` has to be called regularly (e.g. on every animation frame).
+pub struct GestureDetector {
+    window_ms: u32,
+    pending: Option<(ButtonDirection, u32)>,
+}
+
+impl GestureDetector {
+    pub fn new(window_ms: u32) -> Self {
+        Self {
+            window_ms,
+            pending: None,
+        }
+    }
+
+    pub fn press(&mut self, direction: ButtonDirection, now_ms: u32) -> Option<Gesture> {
+        match self.pending.take() {
+            Some((pending, at))
+                if pending == direction && now_ms.wrapping_sub(at) <= self.window_ms =>
+            {
+                Some(Gesture::DoublePress(direction))
+            }
+            other => {
+                self.pending = Some((direction, now_ms));
+                other.map(|(pending, _)| Gesture::Press(pending))
+            }
+        }
+    }
+
+    pub fn poll(&mut self, now_ms: u32) -> Option<Gesture> {
+        match self.pending {
+            Some((direction, at)) if now_ms.wrapping_sub(at) > self.window_ms => {
+                self.pending = None;
+                Some(Gesture::Press(direction))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Default for GestureDetector {
+    fn default() -> Self {
+        Self::new(DOUBLE_PRESS_WINDOW_MS)
+    }
+}
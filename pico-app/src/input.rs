@@ -0,0 +1,118 @@
+use crate::button::ButtonDirection;
+use crate::runtime::EventReceiver;
+
+// Logical buttons, independent of the pin they are wired to. A five-way
+// joystick is Up/Down/Left/Right plus Select, any other key (e.g. on a
+// keypad) is identified by its character.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Button {
+    Left,
+    Right,
+    Up,
+    Down,
+    Select,
+    Key(u8),
+}
+
+impl Button {
+    // The direction the LED row demos use, for buttons that have one.
+    pub fn direction(self) -> Option<ButtonDirection> {
+        match self {
+            Button::Left => Some(ButtonDirection::Left),
+            Button::Right => Some(ButtonDirection::Right),
+            Button::Up | Button::Down | Button::Select | Button::Key(_) => None,
+        }
+    }
+}
+
+impl From<ButtonDirection> for Button {
+    fn from(direction: ButtonDirection) -> Self {
+        match direction {
+            ButtonDirection::Left => Button::Left,
+            ButtonDirection::Right => Button::Right,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Action {
+    Press,
+    Release,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InputEvent {
+    pub button: Button,
+    pub action: Action,
+    // `Clock::now_ms` when the change happened, or was first seen
+    pub timestamp_ms: u32,
+}
+
+// Maps GPIO numbers to logical buttons. Meant to live in a `const`, so a
+// duplicate pin is a compile error.
+pub struct Keymap<const N: usize> {
+    bindings: [(u8, Button); N],
+}
+
+impl<const N: usize> Keymap<N> {
+    pub const fn new(bindings: [(u8, Button); N]) -> Self {
+        let mut i = 0;
+        while i < N {
+            let mut j = i + 1;
+            while j < N {
+                assert!(bindings[i].0 != bindings[j].0, "GPIO mapped twice");
+                j += 1;
+            }
+            i += 1;
+        }
+        Self { bindings }
+    }
+
+    pub const fn button(&self, gpio: u8) -> Option<Button> {
+        let mut i = 0;
+        while i < N {
+            if self.bindings[i].0 == gpio {
+                return Some(self.bindings[i].1);
+            }
+            i += 1;
+        }
+        None
+    }
+
+    pub fn bindings(&self) -> &[(u8, Button); N] {
+        &self.bindings
+    }
+
+    // Number of buttons, e.g. how many input tasks a firmware needs.
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+}
+
+// The two buttons of the demo board, shared by both firmwares. More buttons
+// only need an entry here and an input in the firmware's `main`.
+pub const KEYMAP: Keymap<2> = Keymap::new([(10, Button::Left), (11, Button::Right)]);
+
+// Turns a stream of input events into the button directions the LED tasks
+// expect: presses of Left and Right, everything else is dropped.
+pub struct Directions<R>(pub R);
+
+impl<R: EventReceiver<InputEvent>> EventReceiver<ButtonDirection> for Directions<R> {
+    async fn receive(&mut self) -> Option<ButtonDirection> {
+        loop {
+            let event = self.0.receive().await?;
+            if event.action == Action::Press
+                && let Some(direction) = event.button.direction()
+            {
+                return Some(direction);
+            }
+        }
+    }
+}
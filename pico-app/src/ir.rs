@@ -0,0 +1,281 @@
+use crate::input::Button;
+
+// IR receiver modules (TSOP38 and friends) pull their output low while they
+// see the 38 kHz carrier. The decoders are fed the pulses between two edges:
+// a mark is a burst of carrier, a space the pause between bursts.
+
+// ±25 % around the nominal length still counts
+fn is_about(duration_us: u32, nominal_us: u32) -> bool {
+    let tolerance = nominal_us / 4;
+    duration_us.abs_diff(nominal_us) <= tolerance
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Protocol {
+    Nec,
+    Rc5,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IrCommand {
+    pub protocol: Protocol,
+    pub address: u16,
+    pub command: u8,
+    // the key is still held, the remote repeats the last command
+    pub repeat: bool,
+}
+
+pub trait IrDecoder {
+    // Takes the next pulse, `mark` tells whether it was a carrier burst.
+    // Returns a command once a frame is complete.
+    fn update(&mut self, mark: bool, duration_us: u32) -> Option<IrCommand>;
+}
+
+const NEC_LEADER_MARK_US: u32 = 9000;
+const NEC_LEADER_SPACE_US: u32 = 4500;
+const NEC_REPEAT_SPACE_US: u32 = 2250;
+const NEC_BIT_MARK_US: u32 = 562;
+const NEC_ZERO_SPACE_US: u32 = 562;
+const NEC_ONE_SPACE_US: u32 = 1687;
+const NEC_BITS: u8 = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum NecState {
+    Idle,
+    LeaderSpace,
+    // mark of the next bit, or the final mark after all 32 bits
+    BitMark { bits: u8, data: u32 },
+    BitSpace { bits: u8, data: u32 },
+    RepeatMark,
+}
+
+// NEC: a 9 ms leader, then address, inverted address, command and inverted
+// command, 8 bits each and LSB first, sent as the length of the space after
+// each mark. A held key sends a short repeat frame instead every 108 ms.
+// Remotes with a 16-bit address (extended NEC) don't invert the address.
+pub struct NecDecoder {
+    state: NecState,
+    last: Option<IrCommand>,
+}
+
+impl NecDecoder {
+    pub const fn new() -> Self {
+        Self {
+            state: NecState::Idle,
+            last: None,
+        }
+    }
+
+    fn frame(&mut self, data: u32) -> Option<IrCommand> {
+        let [address, address_inv, command, command_inv] = data.to_le_bytes();
+        if command != !command_inv {
+            debug!("IR: NEC command check failed, {=u32:x}", data);
+            return None;
+        }
+        let address = if address == !address_inv {
+            address as u16
+        } else {
+            u16::from_le_bytes([address, address_inv])
+        };
+        let command = IrCommand {
+            protocol: Protocol::Nec,
+            address,
+            command,
+            repeat: false,
+        };
+        self.last = Some(command);
+        Some(command)
+    }
+}
+
+impl Default for NecDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IrDecoder for NecDecoder {
+    fn update(&mut self, mark: bool, duration_us: u32) -> Option<IrCommand> {
+        let (state, command) = match (self.state, mark) {
+            (NecState::LeaderSpace, false) if is_about(duration_us, NEC_LEADER_SPACE_US) => {
+                (NecState::BitMark { bits: 0, data: 0 }, None)
+            }
+            (NecState::LeaderSpace, false) if is_about(duration_us, NEC_REPEAT_SPACE_US) => {
+                (NecState::RepeatMark, None)
+            }
+            (NecState::RepeatMark, true) if is_about(duration_us, NEC_BIT_MARK_US) => {
+                let repeat = self.last.map(|last| IrCommand {
+                    repeat: true,
+                    ..last
+                });
+                (NecState::Idle, repeat)
+            }
+            (NecState::BitMark { bits, data }, true) if is_about(duration_us, NEC_BIT_MARK_US) => {
+                if bits == NEC_BITS {
+                    (NecState::Idle, self.frame(data))
+                } else {
+                    (NecState::BitSpace { bits, data }, None)
+                }
+            }
+            (NecState::BitSpace { bits, data }, false) => {
+                let bit = if is_about(duration_us, NEC_ZERO_SPACE_US) {
+                    0
+                } else if is_about(duration_us, NEC_ONE_SPACE_US) {
+                    1
+                } else {
+                    self.state = NecState::Idle;
+                    return None;
+                };
+                let state = NecState::BitMark {
+                    bits: bits + 1,
+                    data: data | bit << bits,
+                };
+                (state, None)
+            }
+            // anything unexpected starts over, possibly with a new leader
+            (_, true) if is_about(duration_us, NEC_LEADER_MARK_US) => (NecState::LeaderSpace, None),
+            _ => (NecState::Idle, None),
+        };
+        self.state = state;
+        command
+    }
+}
+
+const RC5_HALF_BIT_US: u32 = 889;
+const RC5_BITS: u32 = 14;
+
+// RC5: 14 Manchester coded bits of 1.778 ms, MSB first: two start bits, a
+// toggle bit that flips with every new key press, 5 address and 6 command
+// bits. A one is a space followed by a mark. RC5X reuses the second start
+// bit as the inverted 7th command bit.
+pub struct Rc5Decoder {
+    // one bit per half bit period received so far, set for marks
+    halves: u32,
+    count: u32,
+    last_toggle: Option<bool>,
+}
+
+impl Rc5Decoder {
+    pub const fn new() -> Self {
+        Self {
+            halves: 0,
+            count: 0,
+            last_toggle: None,
+        }
+    }
+
+    fn push(&mut self, mark: bool, halves: u32) {
+        for _ in 0..halves {
+            self.halves = self.halves << 1 | mark as u32;
+            self.count += 1;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.halves = 0;
+        self.count = 0;
+    }
+
+    fn frame(&mut self) -> Option<IrCommand> {
+        let mut bits = 0u32;
+        for i in (0..RC5_BITS).rev() {
+            let pair = (self.halves >> (2 * i)) & 0b11;
+            bits = bits << 1
+                | match pair {
+                    0b01 => 1,
+                    0b10 => 0,
+                    // no transition in the middle of a bit
+                    _ => return None,
+                };
+        }
+        let command = (bits & 0x3f) as u8 | ((bits >> 12 & 1 == 0) as u8) << 6;
+        let address = (bits >> 6 & 0x1f) as u16;
+        let toggle = bits >> 11 & 1 != 0;
+        let repeat = self.last_toggle == Some(toggle);
+        self.last_toggle = Some(toggle);
+        Some(IrCommand {
+            protocol: Protocol::Rc5,
+            address,
+            command,
+            repeat,
+        })
+    }
+}
+
+impl Default for Rc5Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IrDecoder for Rc5Decoder {
+    fn update(&mut self, mark: bool, duration_us: u32) -> Option<IrCommand> {
+        let halves = if is_about(duration_us, RC5_HALF_BIT_US) {
+            1
+        } else if is_about(duration_us, 2 * RC5_HALF_BIT_US) {
+            2
+        } else {
+            0
+        };
+
+        if self.count == 0 {
+            // the first half of the first start bit is lost in the idle space
+            if mark && halves > 0 {
+                self.push(false, 1);
+                self.push(true, halves);
+            }
+            return None;
+        }
+        if halves == 0 {
+            debug!("IR: RC5 frame broken off after {} half bits", self.count);
+            self.reset();
+            return None;
+        }
+
+        self.push(mark, halves);
+        // a frame ending in a zero ends with a space, which merges into the
+        // pause after the frame
+        if self.count == 2 * RC5_BITS - 1 && mark {
+            self.push(false, 1);
+        }
+        if self.count < 2 * RC5_BITS {
+            return None;
+        }
+        let command = if self.count == 2 * RC5_BITS {
+            self.frame()
+        } else {
+            None
+        };
+        self.reset();
+        command
+    }
+}
+
+// Maps remote control codes to logical buttons.
+pub struct IrKeymap<const N: usize> {
+    bindings: [(u16, u8, Button); N],
+}
+
+impl<const N: usize> IrKeymap<N> {
+    pub const fn new(bindings: [(u16, u8, Button); N]) -> Self {
+        Self { bindings }
+    }
+
+    pub fn button(&self, command: &IrCommand) -> Option<Button> {
+        self.bindings
+            .iter()
+            .find(|&&(address, code, _)| address == command.address && code == command.command)
+            .map(|&(_, _, button)| button)
+    }
+}
+
+// The small 21-key NEC remotes that come with many Arduino kits, address 0.
+pub const IR_KEYMAP: IrKeymap<5> = IrKeymap::new([
+    (0x00, 0x44, Button::Left),
+    (0x00, 0x40, Button::Right),
+    (0x00, 0x43, Button::Select),
+    (0x00, 0x46, Button::Up),
+    (0x00, 0x15, Button::Down),
+]);
@@ -0,0 +1,207 @@
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{InputPin, OutputPin};
+
+use crate::input::{Action, Button};
+
+pub const KEYPAD_SCAN_MS: u32 = 5;
+// a key has to read the same for this many scans in a row to count
+pub const KEYPAD_DEBOUNCE_SCANS: u8 = 4;
+// time for the column lines to follow a newly selected row
+const SETTLE_US: u32 = 5;
+
+// The usual 4×4 membrane keypad. The digits around 5 double as a joystick.
+pub const KEYPAD_4X4: [[Button; 4]; 4] = [
+    [
+        Button::Key(b'1'),
+        Button::Up,
+        Button::Key(b'3'),
+        Button::Key(b'A'),
+    ],
+    [
+        Button::Left,
+        Button::Select,
+        Button::Right,
+        Button::Key(b'B'),
+    ],
+    [
+        Button::Key(b'7'),
+        Button::Down,
+        Button::Key(b'9'),
+        Button::Key(b'C'),
+    ],
+    [
+        Button::Key(b'*'),
+        Button::Key(b'0'),
+        Button::Key(b'#'),
+        Button::Key(b'D'),
+    ],
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct KeyEvent {
+    pub row: usize,
+    pub column: usize,
+    pub action: Action,
+}
+
+// Without diodes, three pressed keys on the corners of a rectangle make the
+// fourth corner read as pressed too. Two rows sharing two or more pressed
+// columns can't be told apart from that.
+pub fn is_ghosted<const R: usize>(rows: &[u32; R]) -> bool {
+    (0..R).any(|a| (a + 1..R).any(|b| (rows[a] & rows[b]).count_ones() >= 2))
+}
+
+// Per-key debouncing of raw scans, one bit per column in every row.
+pub struct Debouncer<const R: usize, const C: usize> {
+    pressed: [u32; R],
+    counts: [[u8; C]; R],
+    scans: u8,
+}
+
+impl<const R: usize, const C: usize> Debouncer<R, C> {
+    pub const fn new(scans: u8) -> Self {
+        const { assert!(C <= 32, "at most 32 columns") };
+        Self {
+            pressed: [0; R],
+            counts: [[0; C]; R],
+            scans,
+        }
+    }
+
+    pub fn is_pressed(&self, row: usize, column: usize) -> bool {
+        self.pressed[row] & (1 << column) != 0
+    }
+
+    pub fn update(&mut self, raw: &[u32; R]) -> KeyChanges<R> {
+        let mut changed = [0; R];
+        for (row, counts) in self.counts.iter_mut().enumerate() {
+            for (column, count) in counts.iter_mut().enumerate() {
+                let bit = 1 << column;
+                if (raw[row] ^ self.pressed[row]) & bit == 0 {
+                    *count = 0;
+                    continue;
+                }
+                *count += 1;
+                if *count >= self.scans {
+                    *count = 0;
+                    self.pressed[row] ^= bit;
+                    changed[row] |= bit;
+                }
+            }
+        }
+        KeyChanges {
+            changed,
+            pressed: self.pressed,
+            row: 0,
+        }
+    }
+}
+
+// Keys whose debounced state changed in one scan.
+pub struct KeyChanges<const R: usize> {
+    changed: [u32; R],
+    pressed: [u32; R],
+    row: usize,
+}
+
+impl<const R: usize> KeyChanges<R> {
+    pub const fn none() -> Self {
+        Self {
+            changed: [0; R],
+            pressed: [0; R],
+            row: R,
+        }
+    }
+}
+
+impl<const R: usize> Iterator for KeyChanges<R> {
+    type Item = KeyEvent;
+
+    fn next(&mut self) -> Option<KeyEvent> {
+        while self.row < R {
+            let changed = self.changed[self.row];
+            if changed == 0 {
+                self.row += 1;
+                continue;
+            }
+            let column = changed.trailing_zeros() as usize;
+            self.changed[self.row] &= !(1 << column);
+            let action = if self.pressed[self.row] & (1 << column) != 0 {
+                Action::Press
+            } else {
+                Action::Release
+            };
+            return Some(KeyEvent {
+                row: self.row,
+                column,
+                action,
+            });
+        }
+        None
+    }
+}
+
+// Scans a matrix keypad: each row in turn is driven low while the pulled-up
+// columns are read, a low column means the key at the crossing is pressed.
+// Unselected rows are driven high.
+pub struct Keypad<O, I, DL, const R: usize, const C: usize> {
+    rows: [O; R],
+    columns: [I; C],
+    delay: DL,
+    debouncer: Debouncer<R, C>,
+}
+
+impl<O, I, DL, const R: usize, const C: usize> Keypad<O, I, DL, R, C>
+where
+    O: OutputPin,
+    I: InputPin,
+    DL: DelayNs,
+{
+    pub fn new(mut rows: [O; R], columns: [I; C], delay: DL) -> Self {
+        for row in rows.iter_mut() {
+            row.set_high().ok();
+        }
+        Self {
+            rows,
+            columns,
+            delay,
+            debouncer: Debouncer::new(KEYPAD_DEBOUNCE_SCANS),
+        }
+    }
+
+    pub fn is_pressed(&self, row: usize, column: usize) -> bool {
+        self.debouncer.is_pressed(row, column)
+    }
+
+    // One bit per pressed column for every row, not debounced.
+    pub fn scan_raw(&mut self) -> [u32; R] {
+        let mut raw = [0; R];
+        for (row, pressed) in self.rows.iter_mut().zip(raw.iter_mut()) {
+            row.set_low().ok();
+            self.delay.delay_us(SETTLE_US);
+            for (column, input) in self.columns.iter_mut().enumerate() {
+                if input.is_low().unwrap_or(false) {
+                    *pressed |= 1 << column;
+                }
+            }
+            row.set_high().ok();
+        }
+        raw
+    }
+
+    // Scans once and reports the debounced changes. Ghosted scans are
+    // dropped, the keys keep their previous state.
+    pub fn scan(&mut self) -> KeyChanges<R> {
+        let raw = self.scan_raw();
+        if is_ghosted(&raw) {
+            debug!("KEYPAD: ghosting, scan ignored");
+            return KeyChanges::none();
+        }
+        self.debouncer.update(&raw)
+    }
+
+    pub fn release(self) -> ([O; R], [I; C], DL) {
+        (self.rows, self.columns, self.delay)
+    }
+}
@@ -0,0 +1,382 @@
+use core::cell::Cell;
+use core::future::poll_fn;
+use core::ops::Range;
+use core::task::{Poll, Waker};
+
+use embedded_hal::digital::{PinState, StatefulOutputPin};
+
+use crate::button::ButtonDirection;
+use crate::framebuffer::Display;
+use crate::oneshot::{self, Oneshot};
+
+// Plain GPIO LEDs are either on or off, brighter pixels switch them on.
+pub const ON_THRESHOLD: u8 = 128;
+
+// How an LED is wired: with the cathode to ground it lights up when the pin
+// is driven high, with a common anode it lights up when driven low.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Polarity {
+    #[default]
+    ActiveHigh,
+    ActiveLow,
+}
+
+impl Polarity {
+    pub fn state(self, on: bool) -> PinState {
+        match self {
+            Polarity::ActiveHigh => PinState::from(on),
+            Polarity::ActiveLow => PinState::from(!on),
+        }
+    }
+
+    pub fn is_on(self, state: PinState) -> bool {
+        state == self.state(true)
+    }
+}
+
+// What a button press does to the selection.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SelectionMode {
+    // the selection moves along the row
+    #[default]
+    Move,
+    // the row is a level meter: right grows the selection, left shrinks it
+    Meter,
+}
+
+// What happens when a moving selection reaches either end of the row.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EdgeMode {
+    #[default]
+    Wrap,
+    Clamp,
+}
+
+// A row of up to 32 LEDs with a selection (bit n selects LED n) that can be
+// switched on and off, moved along the row or resized. All other LEDs stay
+// off. Initially only the first LED is selected.
+pub struct LedRow<P, const N: usize> {
+    leds: [P; N],
+    selection: u32,
+    polarity: Polarity,
+    mode: SelectionMode,
+    edge: EdgeMode,
+}
+
+impl<P: StatefulOutputPin, const N: usize> LedRow<P, N> {
+    const ALL: u32 = u32::MAX >> (32 - N);
+
+    pub fn new(leds: [P; N]) -> Self {
+        Self::with_polarity(leds, Polarity::ActiveHigh)
+    }
+
+    pub fn with_polarity(leds: [P; N], polarity: Polarity) -> Self {
+        const { assert!(N > 0 && N <= 32, "LedRow needs between 1 and 32 LEDs") };
+        let mut row = Self {
+            leds,
+            selection: 1,
+            polarity,
+            mode: SelectionMode::Move,
+            edge: EdgeMode::Wrap,
+        };
+        for index in 0..N {
+            row.write(index, false);
+        }
+        row
+    }
+
+    // Lowest selected LED, 0 if nothing is selected.
+    pub fn active_led(&self) -> usize {
+        match self.selection {
+            0 => 0,
+            selection => selection.trailing_zeros() as usize,
+        }
+    }
+
+    pub fn mask(&self) -> u32 {
+        self.selection
+    }
+
+    pub fn polarity(&self) -> Polarity {
+        self.polarity
+    }
+
+    pub fn set_mode(&mut self, mode: SelectionMode) {
+        self.mode = mode;
+    }
+
+    pub fn set_edge_mode(&mut self, edge: EdgeMode) {
+        self.edge = edge;
+    }
+
+    pub fn release(self) -> [P; N] {
+        self.leds
+    }
+
+    pub fn set_on(&mut self) {
+        self.write_selection(true);
+    }
+
+    pub fn set_off(&mut self) {
+        self.write_selection(false);
+    }
+
+    // Whether the lowest selected LED is lit.
+    pub fn is_on(&mut self) -> bool {
+        let high = self.leds[self.active_led()].is_set_high().unwrap_or(false);
+        self.polarity.is_on(PinState::from(high))
+    }
+
+    // Switches the current selection off and lights the LEDs in `mask`; bits
+    // beyond the end of the row are ignored.
+    pub fn set_mask(&mut self, mask: u32) {
+        let mask = mask & Self::ALL;
+        for index in 0..N {
+            if self.selection & !mask & (1 << index) != 0 {
+                self.write(index, false);
+            }
+        }
+        self.selection = mask;
+        self.set_on();
+    }
+
+    pub fn set_range(&mut self, range: Range<usize>) {
+        let start = range.start.min(N);
+        let mask = match range.end.clamp(start, N) - start {
+            0 => 0,
+            len => (u32::MAX >> (32 - len)) << start,
+        };
+        self.set_mask(mask);
+    }
+
+    pub fn set_active(&mut self, index: usize) {
+        assert!(index < N, "LED index {} out of range", index);
+        self.set_mask(1 << index);
+    }
+
+    // Number of selected LEDs, the height of the level meter.
+    pub fn level(&self) -> usize {
+        self.selection.count_ones() as usize
+    }
+
+    // Lights the first `level` LEDs.
+    pub fn set_level(&mut self, level: usize) {
+        self.set_range(0..level);
+    }
+
+    // Shows `value` out of `max` on the level meter, rounded to the nearest LED.
+    pub fn show_level(&mut self, value: u32, max: u32) {
+        let max = max.max(1) as u64;
+        let level = (value.min(max as u32) as u64 * N as u64 + max / 2) / max;
+        self.set_level(level as usize);
+    }
+
+    pub fn shift(&mut self, direction: ButtonDirection) {
+        info!("LED ROW: shifting led to direction {}", direction);
+        match self.mode {
+            SelectionMode::Move => self.set_mask(self.moved(direction)),
+            SelectionMode::Meter => self.resize(direction),
+        }
+    }
+
+    // Grows (right) or shrinks (left) the selection at its upper end. The
+    // size never wraps around.
+    pub fn resize(&mut self, direction: ButtonDirection) {
+        let top = (32 - self.selection.leading_zeros()) as usize;
+        let mask = match direction {
+            ButtonDirection::Right if top < N => self.selection | (1 << top),
+            ButtonDirection::Left if top > 0 => self.selection & !(1 << (top - 1)),
+            _ => self.selection,
+        };
+        self.set_mask(mask);
+    }
+
+    pub fn toggle(&mut self) {
+        info!("LED ROW: toggling leds {=u32:b}", self.selection);
+        for (index, led) in self.leds.iter_mut().enumerate() {
+            if self.selection & (1 << index) != 0 {
+                led.toggle().ok();
+            }
+        }
+    }
+
+    fn moved(&self, direction: ButtonDirection) -> u32 {
+        let selection = self.selection;
+        let last = 1 << (N - 1);
+        match (direction, self.edge) {
+            (ButtonDirection::Left, EdgeMode::Clamp) if selection & 1 != 0 => selection,
+            (ButtonDirection::Right, EdgeMode::Clamp) if selection & last != 0 => selection,
+            (ButtonDirection::Left, _) => (selection >> 1) | ((selection & 1) << (N - 1)),
+            (ButtonDirection::Right, _) => ((selection << 1) & Self::ALL) | (selection >> (N - 1)),
+        }
+    }
+
+    fn write_selection(&mut self, on: bool) {
+        for index in 0..N {
+            if self.selection & (1 << index) != 0 {
+                self.write(index, on);
+            }
+        }
+    }
+
+    fn write(&mut self, index: usize, on: bool) {
+        self.leds[index].set_state(self.polarity.state(on)).ok();
+    }
+}
+
+impl<P: StatefulOutputPin, const N: usize> Display<N, 1> for LedRow<P, N> {
+    fn write_pixel(&mut self, x: usize, _y: usize, level: u8) {
+        self.write(x, level >= ON_THRESHOLD);
+    }
+}
+
+// Why `LedControl::active_led` has no answer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum QueryError {
+    // the LED mode has no LED task to ask
+    NoLedTask,
+    // another request, e.g. from the other console, still waits for its answer
+    Busy,
+    // the LED task dropped the request
+    NoAnswer,
+}
+
+// Lets another task, e.g. the serial console, steer `led_task`: select an
+// LED directly, change the blink period or ask for the active LED. Changes
+// wake the LED task, a new blink period wakes `blink_task`.
+pub struct LedControl<'a> {
+    len: usize,
+    led: Cell<Option<usize>>,
+    blink_ms: Cell<u32>,
+    changed: Signal,
+    blink_changed: Signal,
+    // whether an LED task follows the changes
+    attached: Cell<bool>,
+    // the slot for `active_led` requests and the sender the LED task answers with
+    active_led: &'a Oneshot<usize>,
+    query: Cell<Option<oneshot::Sender<'a, usize>>>,
+}
+
+impl<'a> LedControl<'a> {
+    pub const fn new(len: usize, blink_ms: u32, active_led: &'a Oneshot<usize>) -> Self {
+        Self {
+            len,
+            led: Cell::new(None),
+            blink_ms: Cell::new(blink_ms),
+            changed: Signal::new(),
+            blink_changed: Signal::new(),
+            attached: Cell::new(false),
+            active_led,
+            query: Cell::new(None),
+        }
+    }
+
+    // Number of LEDs in the row.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Called by the LED task once it follows the changes.
+    pub fn attach(&self) {
+        self.attached.set(true);
+    }
+
+    pub fn is_attached(&self) -> bool {
+        self.attached.get()
+    }
+
+    pub fn select(&self, led: usize) {
+        self.led.set(Some(led));
+        self.notify();
+    }
+
+    pub fn blink_ms(&self) -> u32 {
+        self.blink_ms.get()
+    }
+
+    pub fn set_blink_ms(&self, blink_ms: u32) {
+        self.blink_ms.set(blink_ms);
+        self.blink_changed.notify();
+    }
+
+    // The LED selected since the last call, if any.
+    pub fn take_led(&self) -> Option<usize> {
+        self.led.take()
+    }
+
+    // Asks the LED task which LED is active. There is one request at a
+    // time, the LED task answers them in turn.
+    pub async fn active_led(&self) -> Result<usize, QueryError> {
+        if !self.is_attached() {
+            return Err(QueryError::NoLedTask);
+        }
+        let (sender, receiver) = oneshot::channel(self.active_led).ok_or(QueryError::Busy)?;
+        self.query.set(Some(sender));
+        self.notify();
+        receiver.await.map_err(|_| QueryError::NoAnswer)
+    }
+
+    // The pending `active_led` request, for the LED task to answer.
+    pub fn take_query(&self) -> Option<oneshot::Sender<'a, usize>> {
+        self.query.take()
+    }
+
+    fn notify(&self) {
+        self.changed.notify();
+    }
+
+    // Resolves once the LED selection or a request changed since the last
+    // time it resolved.
+    pub async fn changed(&self) {
+        self.changed.wait().await
+    }
+
+    // Resolves once the blink period changed since the last time it
+    // resolved. Separate from `changed`, so the blink task can wait for it
+    // next to the LED task.
+    pub async fn blink_changed(&self) {
+        self.blink_changed.wait().await
+    }
+}
+
+// A change one task waits for.
+struct Signal {
+    set: Cell<bool>,
+    waker: Cell<Option<Waker>>,
+}
+
+impl Signal {
+    const fn new() -> Self {
+        Self {
+            set: Cell::new(false),
+            waker: Cell::new(None),
+        }
+    }
+
+    fn notify(&self) {
+        self.set.set(true);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    async fn wait(&self) {
+        poll_fn(|cx| {
+            if self.set.replace(false) {
+                Poll::Ready(())
+            } else {
+                self.waker.set(Some(cx.waker().clone()));
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
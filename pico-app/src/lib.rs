@@ -0,0 +1,33 @@
+#![no_std]
+
+#[macro_use]
+mod fmt;
+
+pub mod animation;
+pub mod button;
+pub mod channel;
+pub mod console;
+pub mod encoder;
+pub mod font;
+pub mod framebuffer;
+pub mod game;
+pub mod gesture;
+pub mod input;
+pub mod ir;
+pub mod keypad;
+pub mod led;
+pub mod matrix;
+pub mod oneshot;
+pub mod pwm;
+#[cfg(feature = "remote")]
+pub mod remote;
+pub mod runtime;
+pub mod sync;
+pub mod tasks;
+pub mod text;
+pub mod ws2812;
+
+pub use button::ButtonDirection;
+pub use framebuffer::FrameBuffer;
+pub use led::LedRow;
+pub use pwm::PwmLedRow;
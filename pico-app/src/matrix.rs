@@ -0,0 +1,150 @@
+use embedded_hal::digital::{OutputPin, PinState};
+
+use crate::framebuffer::FrameBuffer;
+use crate::led::ON_THRESHOLD;
+
+// A display that can only light one line (row, or anode in a charlieplexed
+// setup) at a time and relies on being scanned fast enough for the eye to
+// see the whole frame.
+pub trait ScanDisplay<const W: usize, const H: usize> {
+    fn lines(&self) -> usize;
+    fn show_line(&mut self, frame: &FrameBuffer<W, H>, line: usize);
+    fn blank(&mut self);
+}
+
+// Time each line stays lit to refresh the whole display `refresh_hz` times
+// per second.
+pub fn line_period_us(refresh_hz: u32, lines: usize) -> u32 {
+    1_000_000 / (refresh_hz * lines as u32).max(1)
+}
+
+// Row/column multiplexed matrix as on the micro:bit: a row is selected by
+// driving it high, the LEDs of that row light up where their column is low.
+pub struct LedMatrix<R, C, const W: usize, const H: usize> {
+    rows: [R; H],
+    columns: [C; W],
+    active_row: Option<usize>,
+}
+
+impl<R: OutputPin, C: OutputPin, const W: usize, const H: usize> LedMatrix<R, C, W, H> {
+    pub fn new(rows: [R; H], columns: [C; W]) -> Self {
+        let mut matrix = Self {
+            rows,
+            columns,
+            active_row: None,
+        };
+        for row in matrix.rows.iter_mut() {
+            row.set_low().ok();
+        }
+        for column in matrix.columns.iter_mut() {
+            column.set_high().ok();
+        }
+        matrix
+    }
+}
+
+impl<R: OutputPin, C: OutputPin, const W: usize, const H: usize> ScanDisplay<W, H>
+    for LedMatrix<R, C, W, H>
+{
+    fn lines(&self) -> usize {
+        H
+    }
+
+    fn show_line(&mut self, frame: &FrameBuffer<W, H>, line: usize) {
+        self.blank();
+        for (x, column) in self.columns.iter_mut().enumerate() {
+            let lit = frame.get(x, line) >= ON_THRESHOLD;
+            column.set_state(PinState::from(!lit)).ok();
+        }
+        self.rows[line].set_high().ok();
+        self.active_row = Some(line);
+    }
+
+    fn blank(&mut self) {
+        if let Some(row) = self.active_row.take() {
+            self.rows[row].set_low().ok();
+        }
+    }
+}
+
+// A pin that can also be disconnected from the circuit, as needed for
+// charlieplexing.
+pub trait TriStatePin {
+    fn set_high_impedance(&mut self);
+    fn drive(&mut self, state: PinState);
+}
+
+// `n` pins can drive n·(n-1) LEDs, one for every ordered pair of pins. LED
+// `index` is lit by driving `anode` high and `cathode` low.
+pub fn charlieplex_pins(index: usize, pins: usize) -> (usize, usize) {
+    let anode = index / (pins - 1);
+    let cathode = index % (pins - 1);
+    let cathode = if cathode < anode {
+        cathode
+    } else {
+        cathode + 1
+    };
+    (anode, cathode)
+}
+
+pub fn charlieplex_index(anode: usize, cathode: usize, pins: usize) -> usize {
+    let offset = if cathode < anode {
+        cathode
+    } else {
+        cathode - 1
+    };
+    anode * (pins - 1) + offset
+}
+
+// Pixels are numbered row by row; one anode is driven per line.
+pub struct Charlieplex<P, const PINS: usize> {
+    pins: [P; PINS],
+}
+
+impl<P: TriStatePin, const PINS: usize> Charlieplex<P, PINS> {
+    pub fn new(pins: [P; PINS]) -> Self {
+        const { assert!(PINS >= 2, "charlieplexing needs at least two pins") };
+        let mut charlieplex = Self { pins };
+        charlieplex.blank();
+        charlieplex
+    }
+
+    pub fn blank(&mut self) {
+        for pin in self.pins.iter_mut() {
+            pin.set_high_impedance();
+        }
+    }
+}
+
+impl<P: TriStatePin, const PINS: usize, const W: usize, const H: usize> ScanDisplay<W, H>
+    for Charlieplex<P, PINS>
+{
+    fn lines(&self) -> usize {
+        PINS
+    }
+
+    fn show_line(&mut self, frame: &FrameBuffer<W, H>, line: usize) {
+        const {
+            assert!(
+                W * H <= PINS * (PINS - 1),
+                "frame too large for charlieplexed pins"
+            )
+        };
+        self.blank();
+        let mut any_lit = false;
+        for cathode in (0..PINS).filter(|&cathode| cathode != line) {
+            let index = charlieplex_index(line, cathode, PINS);
+            if index < W * H && frame.get(index % W, index / W) >= ON_THRESHOLD {
+                self.pins[cathode].drive(PinState::Low);
+                any_lit = true;
+            }
+        }
+        if any_lit {
+            self.pins[line].drive(PinState::High);
+        }
+    }
+
+    fn blank(&mut self) {
+        Charlieplex::blank(self);
+    }
+}
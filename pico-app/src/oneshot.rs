@@ -0,0 +1,151 @@
+use core::{
+    cell::{Cell, RefCell},
+    future::{Future, poll_fn},
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Canceled;
+
+// There is no allocator in the firmwares, so the shared state of a oneshot
+// channel lives in a `Oneshot` slot owned by the caller, somewhere that
+// outlives both ends.
+pub struct Oneshot<T> {
+    item: Cell<Option<T>>,
+    sender_alive: Cell<bool>,
+    receiver_alive: Cell<bool>,
+    receiver_waker: RefCell<Option<Waker>>,
+    sender_waker: RefCell<Option<Waker>>,
+}
+
+impl<T> Oneshot<T> {
+    pub const fn new() -> Self {
+        Self {
+            item: Cell::new(None),
+            sender_alive: Cell::new(false),
+            receiver_alive: Cell::new(false),
+            receiver_waker: RefCell::new(None),
+            sender_waker: RefCell::new(None),
+        }
+    }
+
+    // Whether a sender or receiver of the last pair is still around.
+    pub fn is_in_use(&self) -> bool {
+        self.sender_alive.get() || self.receiver_alive.get()
+    }
+
+    fn wake_receiver(&self) {
+        if let Some(waker) = self.receiver_waker.borrow().as_ref() {
+            waker.wake_by_ref();
+        }
+    }
+
+    fn wake_sender(&self) {
+        if let Some(waker) = self.sender_waker.borrow().as_ref() {
+            waker.wake_by_ref();
+        }
+    }
+}
+
+impl<T> Default for Oneshot<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Hands out a new sender/receiver pair using `slot`, `None` while one end of
+// the previous pair still uses it. The slot is reset, so it can be reused
+// for one request after the other.
+pub fn channel<T>(slot: &Oneshot<T>) -> Option<(Sender<'_, T>, Receiver<'_, T>)> {
+    if slot.is_in_use() {
+        return None;
+    }
+    slot.item.set(None);
+    slot.receiver_waker.replace(None);
+    slot.sender_waker.replace(None);
+    slot.sender_alive.set(true);
+    slot.receiver_alive.set(true);
+    Some((Sender { slot }, Receiver { slot }))
+}
+
+pub struct Sender<'a, T> {
+    slot: &'a Oneshot<T>,
+}
+
+impl<T> Sender<'_, T> {
+    pub fn send(self, item: T) -> Result<(), T> {
+        if !self.slot.receiver_alive.get() {
+            return Err(item);
+        }
+        self.slot.item.set(Some(item));
+        // dropping `self` marks the sender as gone and wakes the receiver
+        Ok(())
+    }
+
+    pub fn is_canceled(&self) -> bool {
+        !self.slot.receiver_alive.get()
+    }
+
+    pub async fn canceled(&mut self) {
+        poll_fn(|cx| {
+            if self.is_canceled() {
+                Poll::Ready(())
+            } else {
+                self.slot.sender_waker.replace(Some(cx.waker().clone()));
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+impl<T> Drop for Sender<'_, T> {
+    fn drop(&mut self) {
+        self.slot.sender_alive.set(false);
+        self.slot.sender_waker.replace(None);
+        self.slot.wake_receiver();
+    }
+}
+
+pub struct Receiver<'a, T> {
+    slot: &'a Oneshot<T>,
+}
+
+impl<T> Receiver<'_, T> {
+    pub fn try_receive(&mut self) -> Result<Option<T>, Canceled> {
+        match self.slot.item.take() {
+            Some(item) => Ok(Some(item)),
+            None if self.slot.sender_alive.get() => Ok(None),
+            None => Err(Canceled),
+        }
+    }
+
+    pub fn close(&mut self) {
+        self.slot.receiver_alive.set(false);
+        self.slot.wake_sender();
+    }
+}
+
+impl<T> Future for Receiver<'_, T> {
+    type Output = Result<T, Canceled>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.try_receive() {
+            Ok(Some(item)) => Poll::Ready(Ok(item)),
+            Ok(None) => {
+                self.slot.receiver_waker.replace(Some(cx.waker().clone()));
+                Poll::Pending
+            }
+            Err(canceled) => Poll::Ready(Err(canceled)),
+        }
+    }
+}
+
+impl<T> Drop for Receiver<'_, T> {
+    fn drop(&mut self) {
+        self.slot.receiver_waker.replace(None);
+        self.close();
+    }
+}
@@ -0,0 +1,100 @@
+use embedded_hal::pwm::SetDutyCycle;
+
+use crate::framebuffer::Display;
+use crate::runtime::Delay;
+
+pub const FADE_STEP_MS: u32 = 10;
+
+// Perceived brightness grows roughly with the duty cycle to the power of 2.5.
+// Averaging the squared and cubed level gets close enough without floats.
+pub fn gamma(level: u8) -> u16 {
+    const MAX: u64 = 255 * 255 * 255;
+    let x = level as u64;
+    let curve = (x * x * 255 + x * x * x) / 2;
+    (curve * u16::MAX as u64 / MAX) as u16
+}
+
+pub fn duty_cycle(level: u8, max_duty: u16) -> u16 {
+    (gamma(level) as u32 * max_duty as u32 / u16::MAX as u32) as u16
+}
+
+// Linear interpolation between two brightness levels.
+pub fn fade_level(from: u8, to: u8, elapsed_ms: u32, duration_ms: u32) -> u8 {
+    if elapsed_ms >= duration_ms {
+        return to;
+    }
+    let from = from as i64;
+    let to = to as i64;
+    let level = from + (to - from) * elapsed_ms as i64 / duration_ms as i64;
+    level as u8
+}
+
+// Triangle wave: dark at the start of a period, fully lit halfway through.
+pub fn breathe_level(phase_ms: u32, period_ms: u32) -> u8 {
+    let half = (period_ms / 2).max(1);
+    let phase = phase_ms % period_ms.max(1);
+    if phase < half {
+        fade_level(0, u8::MAX, phase, half)
+    } else {
+        fade_level(u8::MAX, 0, phase - half, half)
+    }
+}
+
+pub struct PwmLedRow<P, const N: usize> {
+    leds: [P; N],
+    levels: [u8; N],
+}
+
+impl<P: SetDutyCycle, const N: usize> PwmLedRow<P, N> {
+    pub fn new(leds: [P; N]) -> Self {
+        let mut row = Self {
+            leds,
+            levels: [0; N],
+        };
+        for index in 0..N {
+            row.set_brightness(index, 0);
+        }
+        row
+    }
+
+    pub fn brightness(&self, index: usize) -> u8 {
+        self.levels[index]
+    }
+
+    pub fn set_brightness(&mut self, index: usize, level: u8) {
+        let led = &mut self.leds[index];
+        let duty = duty_cycle(level, led.max_duty_cycle());
+        led.set_duty_cycle(duty).ok();
+        self.levels[index] = level;
+    }
+
+    pub async fn fade_to<D: Delay>(
+        &mut self,
+        index: usize,
+        level: u8,
+        duration_ms: u32,
+        delay: &mut D,
+    ) {
+        debug!(
+            "PWM LED ROW: fading led {} to {} in {} ms",
+            index, level, duration_ms
+        );
+        let from = self.levels[index];
+        let steps = (duration_ms / FADE_STEP_MS).max(1);
+        for step in 1..=steps {
+            delay.delay_ms(duration_ms / steps).await;
+            let elapsed = (duration_ms as u64 * step as u64 / steps as u64) as u32;
+            self.set_brightness(index, fade_level(from, level, elapsed, duration_ms));
+        }
+    }
+
+    pub fn release(self) -> [P; N] {
+        self.leds
+    }
+}
+
+impl<P: SetDutyCycle, const N: usize> Display<N, 1> for PwmLedRow<P, N> {
+    fn write_pixel(&mut self, x: usize, _y: usize, level: u8) {
+        self.set_brightness(x, level);
+    }
+}
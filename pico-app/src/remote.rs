@@ -0,0 +1,235 @@
+use core::cell::{Cell, RefCell};
+use core::future::poll_fn;
+use core::task::{Poll, Waker};
+
+use futures::{FutureExt, select_biased};
+use pico_protocol::{
+    ButtonEvent, Error, Frame, FrameReader, MAX_FRAME_LEN, Request, Response, encode,
+};
+
+use crate::console::{MAX_BLINK_MS, MIN_BLINK_MS, SystemInfo};
+use crate::input::{Action, Button, InputEvent};
+use crate::led::LedControl;
+use crate::runtime::{EventReceiver, Serial};
+
+pub const EVENT_LOG_SIZE: usize = 8;
+
+// Input events waiting to go out to a subscribed host. Nothing is kept
+// while nobody is subscribed, and the oldest event makes room when the host
+// doesn't keep up.
+pub struct EventLog {
+    events: RefCell<[Option<InputEvent>; EVENT_LOG_SIZE]>,
+    head: Cell<usize>,
+    len: Cell<usize>,
+    subscribed: Cell<bool>,
+    waker: Cell<Option<Waker>>,
+}
+
+impl EventLog {
+    pub const fn new() -> Self {
+        Self {
+            events: RefCell::new([None; EVENT_LOG_SIZE]),
+            head: Cell::new(0),
+            len: Cell::new(0),
+            subscribed: Cell::new(false),
+            waker: Cell::new(None),
+        }
+    }
+
+    pub fn subscribe(&self, subscribed: bool) {
+        self.subscribed.set(subscribed);
+        if !subscribed {
+            self.len.set(0);
+        }
+    }
+
+    pub fn push(&self, event: InputEvent) {
+        if !self.subscribed.get() {
+            return;
+        }
+        let len = self.len.get();
+        let tail = (self.head.get() + len) % EVENT_LOG_SIZE;
+        self.events.borrow_mut()[tail] = Some(event);
+        if len == EVENT_LOG_SIZE {
+            self.head.set((self.head.get() + 1) % EVENT_LOG_SIZE);
+        } else {
+            self.len.set(len + 1);
+        }
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    // The oldest event, if there is one.
+    pub fn try_next(&self) -> Option<InputEvent> {
+        if self.len.get() == 0 {
+            return None;
+        }
+        let head = self.head.get();
+        self.head.set((head + 1) % EVENT_LOG_SIZE);
+        self.len.set(self.len.get() - 1);
+        self.events.borrow_mut()[head].take()
+    }
+
+    // Resolves with the oldest event, once there is one.
+    pub async fn next(&self) -> InputEvent {
+        poll_fn(|cx| match self.try_next() {
+            Some(event) => Poll::Ready(event),
+            None => {
+                self.waker.set(Some(cx.waker().clone()));
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Passes input events on unchanged and copies them to an `EventLog` on the
+// way, so the host sees the same presses as the LED task.
+pub struct Tap<'a, R> {
+    receiver: R,
+    log: &'a EventLog,
+}
+
+impl<'a, R> Tap<'a, R> {
+    pub fn new(receiver: R, log: &'a EventLog) -> Self {
+        Self { receiver, log }
+    }
+}
+
+impl<R: EventReceiver<InputEvent>> EventReceiver<InputEvent> for Tap<'_, R> {
+    async fn receive(&mut self) -> Option<InputEvent> {
+        let event = self.receiver.receive().await?;
+        self.log.push(event);
+        Some(event)
+    }
+}
+
+impl From<InputEvent> for ButtonEvent {
+    fn from(event: InputEvent) -> Self {
+        let button = match event.button {
+            Button::Left => pico_protocol::Button::Left,
+            Button::Right => pico_protocol::Button::Right,
+            Button::Up => pico_protocol::Button::Up,
+            Button::Down => pico_protocol::Button::Down,
+            Button::Select => pico_protocol::Button::Select,
+            Button::Key(key) => pico_protocol::Button::Key(key),
+        };
+        ButtonEvent {
+            button,
+            pressed: event.action == Action::Press,
+            timestamp_ms: event.timestamp_ms,
+        }
+    }
+}
+
+// Answers one request, the same things the text console can do.
+pub fn handle<I: SystemInfo>(
+    request: Request,
+    control: &LedControl<'_>,
+    info: &I,
+    events: &EventLog,
+) -> Response {
+    match request {
+        Request::Ping => Response::Pong,
+        Request::SetActiveLed(_) | Request::SetBlinkPeriod { .. } if !control.is_attached() => {
+            Response::Error(Error::Unsupported)
+        }
+        Request::SetActiveLed(led) if led as usize >= control.len() => {
+            Response::Error(Error::NoSuchLed)
+        }
+        Request::SetActiveLed(led) => {
+            control.select(led as usize);
+            Response::Ok
+        }
+        Request::SetBlinkPeriod { period_ms }
+            if !(MIN_BLINK_MS..=MAX_BLINK_MS).contains(&period_ms) =>
+        {
+            Response::Error(Error::InvalidBlinkPeriod)
+        }
+        Request::SetBlinkPeriod { period_ms } => {
+            control.set_blink_ms(period_ms);
+            Response::Ok
+        }
+        Request::GetStats => {
+            let stats = info.stats();
+            Response::Stats(pico_protocol::Stats {
+                uptime_ms: stats.uptime_ms,
+                idle_count: stats.idle_count,
+                rx_bytes: stats.rx_bytes,
+                tx_bytes: stats.tx_bytes,
+                rx_dropped: stats.rx_dropped,
+            })
+        }
+        Request::GetTask(id) => {
+            Response::Task(info.task(id as usize).map(|task| pico_protocol::TaskInfo {
+                id,
+                polls: task.polls,
+                finished: task.finished,
+            }))
+        }
+        Request::SubscribeEvents(subscribed) => {
+            events.subscribe(subscribed);
+            Response::Ok
+        }
+    }
+}
+
+async fn send<P: Serial>(port: &mut P, response: &Response) {
+    let mut buffer = [0; MAX_FRAME_LEN];
+    match encode(response, &mut buffer) {
+        Ok(frame) => port.write(frame).await,
+        Err(_) => debug!("REMOTE TASK: response doesn't fit a frame"),
+    }
+}
+
+enum Incoming {
+    Bytes(usize),
+    Event(InputEvent),
+}
+
+// Serves the binary protocol of `pico-protocol` on a serial port: one
+// response per request, plus the input events while the host is subscribed.
+pub async fn remote_task<P, I>(mut port: P, control: &LedControl<'_>, info: &I, events: &EventLog)
+where
+    P: Serial,
+    I: SystemInfo,
+{
+    debug!("REMOTE TASK: called!");
+    let mut reader = FrameReader::new();
+    // room for a full USB packet
+    let mut received = [0; 64];
+    loop {
+        let incoming = select_biased! {
+            len = port.read(&mut received).fuse() => Incoming::Bytes(len),
+            event = events.next().fuse() => Incoming::Event(event),
+        };
+        match incoming {
+            Incoming::Bytes(len) => {
+                let mut bytes = &received[..len];
+                while !bytes.is_empty() {
+                    let (frame, remaining) = reader.feed::<Request>(bytes);
+                    bytes = remaining;
+                    let response = match frame {
+                        Some(Frame::Message(request)) => {
+                            debug!("REMOTE TASK: {}", request);
+                            handle(request, control, info, events)
+                        }
+                        Some(Frame::Invalid) => Response::Error(Error::Malformed),
+                        None => continue,
+                    };
+                    send(&mut port, &response).await;
+                }
+            }
+            Incoming::Event(event) => {
+                send(&mut port, &Response::Event(event.into())).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,61 @@
+// The few things the application needs from an async runtime. Each firmware
+// implements these for its own timer, GPIO and channel types.
+#![allow(async_fn_in_trait)]
+
+pub trait Delay {
+    async fn delay_ms(&mut self, millis: u32);
+}
+
+pub trait Input {
+    async fn wait_for_low(&mut self);
+    async fn wait_for_high(&mut self);
+    fn is_high(&mut self) -> bool;
+
+    // `Clock::now_ms` of the change the last wait returned on, for inputs
+    // that timestamp their edges as they happen.
+    fn changed_ms(&self) -> Option<u32> {
+        None
+    }
+
+    // Resolves once the level differs from the one seen when called.
+    async fn wait_for_change(&mut self) {
+        if self.is_high() {
+            self.wait_for_low().await
+        } else {
+            self.wait_for_high().await
+        }
+    }
+}
+
+pub trait EdgeInput {
+    // Waits for the next level change and returns the new level and when it
+    // happened, in microseconds since boot (wrapping).
+    async fn next_edge(&mut self) -> (bool, u32);
+}
+
+pub trait Serial {
+    // Waits for at least one byte and returns how many were read.
+    async fn read(&mut self, buffer: &mut [u8]) -> usize;
+    // Returns once all of `data` is queued for sending.
+    async fn write(&mut self, data: &[u8]);
+}
+
+pub trait EventSender<T> {
+    // Hands the item back if the receiving side is gone.
+    async fn send(&self, item: T) -> Result<(), T>;
+}
+
+pub trait EventReceiver<T> {
+    // Resolves to `None` once no sender is left.
+    async fn receive(&mut self) -> Option<T>;
+}
+
+pub trait Tick {
+    // Resolves at the next tick of a fixed-rate timer.
+    async fn tick(&mut self);
+}
+
+pub trait Clock {
+    // Milliseconds since boot, wrapping after about 49 days.
+    fn now_ms(&self) -> u32;
+}
@@ -0,0 +1,305 @@
+use core::{
+    cell::{Cell, RefCell, UnsafeCell},
+    future::Future,
+    ops::{Deref, DerefMut},
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+
+use heapless::Deque;
+
+// Waiters past the queue's capacity keep retrying until they find room.
+pub const MAX_WAITERS: usize = 4;
+pub const MAX_READERS: usize = 32;
+
+struct Waiter {
+    id: usize,
+    waker: Waker,
+}
+
+// Waiters are served strictly in arrival order: a waiter may only take its
+// permits once it reaches the front of the queue, so a large request
+// (e.g. a writer) can't be starved by a stream of small ones.
+pub struct Semaphore {
+    permits: Cell<usize>,
+    next_id: Cell<usize>,
+    waiters: RefCell<Deque<Waiter, MAX_WAITERS>>,
+}
+
+impl Semaphore {
+    pub const fn new(permits: usize) -> Self {
+        Self {
+            permits: Cell::new(permits),
+            next_id: Cell::new(0),
+            waiters: RefCell::new(Deque::new()),
+        }
+    }
+
+    pub fn available_permits(&self) -> usize {
+        self.permits.get()
+    }
+
+    pub fn acquire(&self) -> Acquire<'_> {
+        self.acquire_many(1)
+    }
+
+    pub fn acquire_many(&self, permits: usize) -> Acquire<'_> {
+        Acquire {
+            semaphore: self,
+            permits,
+            id: None,
+        }
+    }
+
+    pub fn try_acquire(&self) -> Option<SemaphorePermit<'_>> {
+        self.try_acquire_many(1)
+    }
+
+    pub fn try_acquire_many(&self, permits: usize) -> Option<SemaphorePermit<'_>> {
+        if self.waiters.borrow().is_empty() && self.take(permits) {
+            Some(SemaphorePermit {
+                semaphore: self,
+                permits,
+            })
+        } else {
+            None
+        }
+    }
+
+    pub fn release(&self, permits: usize) {
+        self.permits.set(self.permits.get() + permits);
+        self.wake_front();
+    }
+
+    fn take(&self, permits: usize) -> bool {
+        let available = self.permits.get();
+        if available >= permits {
+            self.permits.set(available - permits);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn wake_front(&self) {
+        // without a free permit the front waiter would only find it taken
+        if self.permits.get() == 0 {
+            return;
+        }
+        if let Some(waiter) = self.waiters.borrow().front() {
+            waiter.waker.wake_by_ref();
+        }
+    }
+}
+
+pub struct Acquire<'a> {
+    semaphore: &'a Semaphore,
+    permits: usize,
+    id: Option<usize>,
+}
+
+impl<'a> Future for Acquire<'a> {
+    type Output = SemaphorePermit<'a>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let semaphore = self.semaphore;
+        let permits = self.permits;
+
+        match self.id {
+            None => {
+                if let Some(permit) = semaphore.try_acquire_many(permits) {
+                    return Poll::Ready(permit);
+                }
+                let id = semaphore.next_id.get();
+                let waiter = Waiter {
+                    id,
+                    waker: cx.waker().clone(),
+                };
+                if semaphore.waiters.borrow_mut().push_back(waiter).is_err() {
+                    // the queue is full, try again after the other ready tasks
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+                semaphore.next_id.set(id.wrapping_add(1));
+                self.id = Some(id);
+                Poll::Pending
+            }
+            Some(id) => {
+                let mut waiters = semaphore.waiters.borrow_mut();
+                let at_front = waiters.front().is_some_and(|waiter| waiter.id == id);
+                if at_front && semaphore.take(permits) {
+                    waiters.pop_front();
+                    drop(waiters);
+                    self.id = None;
+                    // there may be permits left for the next waiter in line
+                    semaphore.wake_front();
+                    Poll::Ready(SemaphorePermit { semaphore, permits })
+                } else {
+                    if let Some(waiter) = waiters.iter_mut().find(|waiter| waiter.id == id) {
+                        waiter.waker.clone_from(cx.waker());
+                    }
+                    Poll::Pending
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Acquire<'_> {
+    fn drop(&mut self) {
+        let Some(id) = self.id else {
+            return;
+        };
+
+        let mut waiters = self.semaphore.waiters.borrow_mut();
+        let was_front = waiters.front().is_some_and(|waiter| waiter.id == id);
+        let len = waiters.len();
+        for _ in 0..len {
+            if let Some(waiter) = waiters.pop_front()
+                && waiter.id != id
+            {
+                waiters.push_back(waiter).ok();
+            }
+        }
+        drop(waiters);
+
+        if was_front {
+            self.semaphore.wake_front();
+        }
+    }
+}
+
+pub struct SemaphorePermit<'a> {
+    semaphore: &'a Semaphore,
+    permits: usize,
+}
+
+impl Drop for SemaphorePermit<'_> {
+    fn drop(&mut self) {
+        self.semaphore.release(self.permits);
+    }
+}
+
+pub struct Mutex<T> {
+    semaphore: Semaphore,
+    value: UnsafeCell<T>,
+}
+
+impl<T> Mutex<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            semaphore: Semaphore::new(1),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub async fn lock(&self) -> MutexGuard<'_, T> {
+        let permit = self.semaphore.acquire().await;
+        MutexGuard {
+            mutex: self,
+            _permit: permit,
+        }
+    }
+
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        self.semaphore.try_acquire().map(|permit| MutexGuard {
+            mutex: self,
+            _permit: permit,
+        })
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+pub struct MutexGuard<'a, T> {
+    mutex: &'a Mutex<T>,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl<T> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the guard holds the only permit of the semaphore
+        unsafe { &*self.mutex.value.get() }
+    }
+}
+
+impl<T> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: the guard holds the only permit of the semaphore
+        unsafe { &mut *self.mutex.value.get() }
+    }
+}
+
+// Readers take a single permit each, a writer takes all of them.
+pub struct RwLock<T> {
+    semaphore: Semaphore,
+    value: UnsafeCell<T>,
+}
+
+impl<T> RwLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            semaphore: Semaphore::new(MAX_READERS),
+            value: UnsafeCell::new(value),
+        }
+    }
+
+    pub async fn read(&self) -> RwLockReadGuard<'_, T> {
+        let permit = self.semaphore.acquire().await;
+        RwLockReadGuard {
+            lock: self,
+            _permit: permit,
+        }
+    }
+
+    pub async fn write(&self) -> RwLockWriteGuard<'_, T> {
+        let permit = self.semaphore.acquire_many(MAX_READERS).await;
+        RwLockWriteGuard {
+            lock: self,
+            _permit: permit,
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value.into_inner()
+    }
+}
+
+pub struct RwLockReadGuard<'a, T> {
+    lock: &'a RwLock<T>,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl<T> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: no writer can hold all permits while this one is taken
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+pub struct RwLockWriteGuard<'a, T> {
+    lock: &'a RwLock<T>,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl<T> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // SAFETY: the guard holds every permit of the semaphore
+        unsafe { &*self.lock.value.get() }
+    }
+}
+
+impl<T> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        // SAFETY: the guard holds every permit of the semaphore
+        unsafe { &mut *self.lock.value.get() }
+    }
+}
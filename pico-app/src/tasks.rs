@@ -0,0 +1,536 @@
+use core::cell::RefCell;
+use core::pin::pin;
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{InputPin, OutputPin, StatefulOutputPin},
+    pwm::SetDutyCycle,
+};
+use futures::future::FusedFuture;
+use futures::{FutureExt, select_biased};
+
+use crate::animation::{Animation, Animator, FRAME_PERIOD_MS};
+use crate::button::ButtonDirection;
+use crate::console::{
+    CONSOLE_LINE_LEN, LineBuffer, LineEvent, PROMPT, Reply, SystemInfo, run_line,
+};
+use crate::encoder::Encoder;
+use crate::framebuffer::{Display, FrameBuffer, Refresher};
+use crate::game::Game;
+use crate::gesture::GestureDetector;
+use crate::input::{Action, Button, InputEvent};
+use crate::ir::{IrDecoder, IrKeymap};
+use crate::keypad::{KEYPAD_SCAN_MS, Keypad};
+use crate::led::{LedControl, LedRow};
+use crate::matrix::ScanDisplay;
+use crate::pwm::{FADE_STEP_MS, PwmLedRow, breathe_level};
+use crate::runtime::{Clock, Delay, EdgeInput, EventReceiver, EventSender, Input, Serial, Tick};
+use crate::sync::Mutex;
+use crate::text::TextDisplay;
+use crate::ws2812::{ColorStrip, StripWriter};
+
+pub const BLINK_PERIOD_MS: u32 = 500;
+pub const DEBOUNCE_MS: u32 = 200;
+pub const REFRESH_PERIOD_MS: u32 = 20;
+
+// Moves the selection of the shared row on button presses; `control` can
+// select an LED and ask for the active one at any time. `blink_task` does
+// the blinking on the same row.
+pub async fn led_task<P, R, const N: usize>(
+    row: &Mutex<LedRow<P, N>>,
+    mut receiver: R,
+    control: &LedControl<'_>,
+) where
+    P: StatefulOutputPin,
+    R: EventReceiver<ButtonDirection>,
+{
+    debug!("LED TASK: called!");
+    row.lock().await.set_on();
+    control.attach();
+    loop {
+        select_biased! {
+            direction = receiver.receive().fuse() => match direction {
+                Some(direction) => {
+                    // the newly active LED starts lit
+                    debug!("LED TASK: shift led");
+                    row.lock().await.shift(direction);
+                }
+                None => break,
+            },
+            _ = control.changed().fuse() => follow_control(row, control).await,
+        }
+    }
+    info!("LED TASK: all button tasks are gone, follow the control only");
+    loop {
+        control.changed().await;
+        follow_control(row, control).await;
+    }
+}
+
+async fn follow_control<P, const N: usize>(row: &Mutex<LedRow<P, N>>, control: &LedControl<'_>)
+where
+    P: StatefulOutputPin,
+{
+    let mut row = row.lock().await;
+    if let Some(led) = control.take_led().filter(|&led| led < N) {
+        debug!("LED TASK: select led {}", led);
+        row.set_active(led);
+    }
+    if let Some(query) = control.take_query() {
+        query.send(row.active_led()).ok();
+    }
+}
+
+// Toggles the selected LEDs of the shared row, at the period `control` asks
+// for. A new period starts over right away, a long one doesn't have to run
+// out first.
+pub async fn blink_task<P, D, const N: usize>(
+    row: &Mutex<LedRow<P, N>>,
+    mut delay: D,
+    control: &LedControl<'_>,
+) where
+    P: StatefulOutputPin,
+    D: Delay,
+{
+    debug!("BLINK TASK: called!");
+    loop {
+        select_biased! {
+            _ = control.blink_changed().fuse() => {
+                debug!("BLINK TASK: new period {} ms", control.blink_ms());
+                continue;
+            }
+            _ = delay.delay_ms(control.blink_ms()).fuse() => {}
+        }
+        debug!("BLINK TASK: toggle led");
+        row.lock().await.toggle();
+    }
+}
+
+// Reports presses and releases of one button, `button` is what the pin is
+// mapped to in the keymap.
+pub async fn input_task<I, S, D, C>(mut input: I, button: Button, sender: S, mut delay: D, clock: C)
+where
+    I: Input,
+    S: EventSender<InputEvent>,
+    D: Delay,
+    C: Clock,
+{
+    debug!("INPUT TASK {}: called!", button);
+    loop {
+        debug!("INPUT TASK {}: wait for input...", button);
+        input.wait_for_low().await;
+        debug!("INPUT TASK {}: send press", button);
+        // prefer the time of the edge over the time the task got to run
+        let pressed_ms = input.changed_ms().unwrap_or_else(|| clock.now_ms());
+        if send_input(&sender, button, Action::Press, pressed_ms)
+            .await
+            .is_err()
+        {
+            break;
+        }
+        debug!("INPUT TASK {}: debounce delay", button);
+        delay.delay_ms(DEBOUNCE_MS).await;
+        debug!("INPUT TASK {}: wait for high pin state...", button);
+        input.wait_for_high().await;
+        debug!("INPUT TASK {}: send release", button);
+        let released_ms = input.changed_ms().unwrap_or_else(|| clock.now_ms());
+        if send_input(&sender, button, Action::Release, released_ms)
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+    info!("INPUT TASK {}: channel closed, stopping", button);
+}
+
+async fn send_input<S>(
+    sender: &S,
+    button: Button,
+    action: Action,
+    timestamp_ms: u32,
+) -> Result<(), InputEvent>
+where
+    S: EventSender<InputEvent>,
+{
+    let event = InputEvent {
+        button,
+        action,
+        timestamp_ms,
+    };
+    sender.send(event).await
+}
+
+// Reports every detent of a rotary encoder as a press and release of the
+// matching Left/Right button, so it can stand in for the two buttons. Fast
+// turns repeat the step, see `Acceleration`.
+pub async fn encoder_task<I, S, C>(mut a: I, mut b: I, mut encoder: Encoder, sender: S, clock: C)
+where
+    I: Input,
+    S: EventSender<InputEvent>,
+    C: Clock,
+{
+    debug!("ENCODER TASK: called!");
+    loop {
+        let now_ms = clock.now_ms();
+        if let Some((direction, steps)) = encoder.update(a.is_high(), b.is_high(), now_ms) {
+            for _ in 0..steps {
+                let button = Button::from(direction);
+                for action in [Action::Press, Action::Release] {
+                    if send_input(&sender, button, action, clock.now_ms())
+                        .await
+                        .is_err()
+                    {
+                        info!("ENCODER TASK: channel closed, stopping");
+                        return;
+                    }
+                }
+            }
+        }
+        select_biased! {
+            _ = a.wait_for_change().fuse() => {}
+            _ = b.wait_for_change().fuse() => {}
+        }
+    }
+}
+
+// Scans a matrix keypad and reports presses and releases of the buttons in
+// `layout`, which has the same rows and columns as the keypad.
+pub async fn keypad_task<O, I, DL, S, D, C, const ROWS: usize, const COLS: usize>(
+    mut keypad: Keypad<O, I, DL, ROWS, COLS>,
+    layout: [[Button; COLS]; ROWS],
+    sender: S,
+    mut delay: D,
+    clock: C,
+) where
+    O: OutputPin,
+    I: InputPin,
+    DL: DelayNs,
+    S: EventSender<InputEvent>,
+    D: Delay,
+    C: Clock,
+{
+    debug!("KEYPAD TASK: called!");
+    loop {
+        for key in keypad.scan() {
+            let button = layout[key.row][key.column];
+            debug!("KEYPAD TASK: {} {}", button, key.action);
+            if send_input(&sender, button, key.action, clock.now_ms())
+                .await
+                .is_err()
+            {
+                info!("KEYPAD TASK: channel closed, stopping");
+                return;
+            }
+        }
+        delay.delay_ms(KEYPAD_SCAN_MS).await;
+    }
+}
+
+// Decodes an IR remote on a receiver module and reports the keys found in
+// `keymap` as a press and release each. Repeat frames of a held key are
+// dropped, one press is one step.
+pub async fn ir_task<E, IR, S, C, const N: usize>(
+    mut input: E,
+    mut decoder: IR,
+    keymap: &IrKeymap<N>,
+    sender: S,
+    clock: C,
+) where
+    E: EdgeInput,
+    IR: IrDecoder,
+    S: EventSender<InputEvent>,
+    C: Clock,
+{
+    debug!("IR TASK: called!");
+    let (_, mut last_us) = input.next_edge().await;
+    loop {
+        let (high, now_us) = input.next_edge().await;
+        let duration_us = now_us.wrapping_sub(last_us);
+        last_us = now_us;
+        // the receiver output is low during a burst, so a rising edge ends a mark
+        let Some(command) = decoder.update(high, duration_us) else {
+            continue;
+        };
+        debug!("IR TASK: {}", command);
+        if command.repeat {
+            continue;
+        }
+        let Some(button) = keymap.button(&command) else {
+            info!(
+                "IR TASK: no key for address {=u16:x}, command {=u8:x}",
+                command.address, command.command
+            );
+            continue;
+        };
+        for action in [Action::Press, Action::Release] {
+            if send_input(&sender, button, action, clock.now_ms())
+                .await
+                .is_err()
+            {
+                info!("IR TASK: channel closed, stopping");
+                return;
+            }
+        }
+    }
+}
+
+// A line-oriented command shell on a serial port, see `console::parse` for
+// the commands.
+pub async fn console_task<P, I>(mut port: P, control: &LedControl<'_>, info: &I)
+where
+    P: Serial,
+    I: SystemInfo,
+{
+    debug!("CONSOLE TASK: called!");
+    let mut line = LineBuffer::<CONSOLE_LINE_LEN>::new();
+    let mut reply = Reply::<512>::new();
+    // room for a full USB packet
+    let mut received = [0; 64];
+    port.write(b"\r\npico console, `help` lists the commands\r\n")
+        .await;
+    port.write(PROMPT.as_bytes()).await;
+    loop {
+        let len = port.read(&mut received).await;
+        for &byte in &received[..len] {
+            match line.push(byte) {
+                LineEvent::Ignored => {}
+                LineEvent::Echo(byte) => port.write(&[byte]).await,
+                LineEvent::Erase => port.write(b"\x08 \x08").await,
+                LineEvent::Done => {
+                    reply.clear();
+                    if run_line(line.as_str(), control, info, &mut reply)
+                        .await
+                        .is_err()
+                    {
+                        debug!("CONSOLE TASK: reply cut off");
+                    }
+                    line.clear();
+                    port.write(b"\r\n").await;
+                    port.write(reply.as_bytes()).await;
+                    port.write(PROMPT.as_bytes()).await;
+                }
+            }
+        }
+    }
+}
+
+pub const BREATHE_PERIOD_MS: u32 = 2 * BLINK_PERIOD_MS;
+
+// Same behaviour as `led_task`, but the active LED fades in and out instead
+// of hard-blinking.
+pub async fn breathing_led_task<P, R, D, const N: usize>(
+    mut leds: PwmLedRow<P, N>,
+    mut receiver: R,
+    mut delay: D,
+) where
+    P: SetDutyCycle,
+    R: EventReceiver<ButtonDirection>,
+    D: Delay,
+{
+    debug!("BREATHING LED TASK: called!");
+    let mut active_led = 0;
+    let mut phase = 0;
+    let mut listening = true;
+    loop {
+        leds.set_brightness(active_led, breathe_level(phase, BREATHE_PERIOD_MS));
+        if !listening {
+            delay.delay_ms(FADE_STEP_MS).await;
+            phase = (phase + FADE_STEP_MS) % BREATHE_PERIOD_MS;
+            continue;
+        }
+        select_biased! {
+            direction = receiver.receive().fuse() => match direction {
+                Some(direction) => {
+                    debug!("BREATHING LED TASK: shift led");
+                    leds.set_brightness(active_led, 0);
+                    active_led = direction.step(active_led, N);
+                    phase = 0;
+                }
+                None => {
+                    info!("BREATHING LED TASK: all button tasks are gone, keep breathing only");
+                    listening = false;
+                }
+            },
+            _ = delay.delay_ms(FADE_STEP_MS).fuse() => {
+                phase = (phase + FADE_STEP_MS) % BREATHE_PERIOD_MS;
+            }
+        }
+    }
+}
+
+// Plays the built-in animations; button gestures select the animation
+// (see `Animator::handle`).
+pub async fn animation_task<Disp, R, D, const N: usize>(
+    mut display: Disp,
+    mut receiver: R,
+    mut delay: D,
+) where
+    Disp: Display<N, 1>,
+    R: EventReceiver<ButtonDirection>,
+    D: Delay,
+{
+    debug!("ANIMATION TASK: called!");
+    let mut animator = Animator::new(Animation::Scanner);
+    let mut gestures = GestureDetector::default();
+    let mut frame = FrameBuffer::<N>::new();
+    let mut refresher = Refresher::new();
+    let mut now_ms: u32 = 0;
+    let mut listening = true;
+    loop {
+        if let Some(gesture) = gestures.poll(now_ms) {
+            animator.handle(gesture);
+        }
+        animator.next_frame(frame.row_mut(0));
+        refresher.refresh(&frame, &mut display);
+
+        // presses only change what the next frame shows, it comes on time
+        let mut frame_delay = pin!(delay.delay_ms(FRAME_PERIOD_MS).fuse());
+        while listening && !frame_delay.is_terminated() {
+            select_biased! {
+                direction = receiver.receive().fuse() => match direction {
+                    Some(direction) => {
+                        if let Some(gesture) = gestures.press(direction, now_ms) {
+                            animator.handle(gesture);
+                        }
+                    }
+                    None => {
+                        info!("ANIMATION TASK: all button tasks are gone, keep animating only");
+                        listening = false;
+                    }
+                },
+                _ = frame_delay => {}
+            }
+        }
+        if !frame_delay.is_terminated() {
+            frame_delay.await;
+        }
+        now_ms = now_ms.wrapping_add(FRAME_PERIOD_MS);
+    }
+}
+
+// Multiplexes the shared frame buffer onto a scanned display, one line per
+// tick. The tick rate sets the refresh rate, see `line_period_us`.
+pub async fn matrix_task<S, T, const W: usize, const H: usize>(
+    frame: &RefCell<FrameBuffer<W, H>>,
+    mut display: S,
+    mut ticker: T,
+) where
+    S: ScanDisplay<W, H>,
+    T: Tick,
+{
+    debug!("MATRIX TASK: called!");
+    let lines = display.lines();
+    loop {
+        for line in 0..lines {
+            let current = *frame.borrow();
+            display.show_line(&current, line);
+            ticker.tick().await;
+        }
+    }
+}
+
+// Scrolls through `messages` on the shared frame buffer; the buttons skip to
+// the previous or next message.
+pub async fn text_task<R, D, const W: usize, const H: usize, const M: usize>(
+    frame: &RefCell<FrameBuffer<W, H>>,
+    messages: &[&str; M],
+    mut receiver: R,
+    delay: D,
+) where
+    R: EventReceiver<ButtonDirection>,
+    D: Delay,
+{
+    const { assert!(M > 0, "text_task needs at least one message") };
+    debug!("TEXT TASK: called!");
+    let mut display = TextDisplay::new(frame, delay);
+    let mut index = 0;
+    let mut listening = true;
+    loop {
+        let text = messages[index];
+        if !listening {
+            display.scroll(text).await;
+            index = ButtonDirection::Right.step(index, messages.len());
+            continue;
+        }
+        select_biased! {
+            direction = receiver.receive().fuse() => match direction {
+                Some(direction) => {
+                    debug!("TEXT TASK: skip message");
+                    index = direction.step(index, messages.len());
+                }
+                None => {
+                    info!("TEXT TASK: all button tasks are gone, keep scrolling only");
+                    listening = false;
+                }
+            },
+            _ = display.scroll(text).fuse() => {
+                index = ButtonDirection::Right.step(index, messages.len());
+            }
+        }
+    }
+}
+
+// Pushes the shared strip buffer out whenever it changed. The borrow is
+// never held across an await point.
+pub async fn strip_task<W, D, const N: usize>(
+    strip: &RefCell<ColorStrip<N>>,
+    mut writer: W,
+    mut delay: D,
+) where
+    W: StripWriter<N>,
+    D: Delay,
+{
+    debug!("STRIP TASK: called!");
+    let mut shown = None;
+    loop {
+        let current = *strip.borrow();
+        if shown != Some(current) {
+            writer.write(&current.encode()).await;
+            shown = Some(current);
+        }
+        delay.delay_ms(REFRESH_PERIOD_MS).await;
+    }
+}
+
+// Plays a game on the row; presses are timestamped with `clock` as soon as
+// they arrive, independent of the frame rate.
+pub async fn game_task<G, Disp, R, D, C, const N: usize>(
+    mut game: G,
+    mut display: Disp,
+    mut receiver: R,
+    mut delay: D,
+    clock: C,
+) where
+    G: Game<N>,
+    Disp: Display<N, 1>,
+    R: EventReceiver<ButtonDirection>,
+    D: Delay,
+    C: Clock,
+{
+    debug!("GAME TASK: called!");
+    let mut frame = FrameBuffer::<N>::new();
+    let mut refresher = Refresher::new();
+    let mut listening = true;
+    loop {
+        let now_ms = clock.now_ms();
+        game.update(now_ms);
+        game.render(now_ms, frame.row_mut(0));
+        refresher.refresh(&frame, &mut display);
+
+        if !listening {
+            delay.delay_ms(FRAME_PERIOD_MS).await;
+            continue;
+        }
+        select_biased! {
+            direction = receiver.receive().fuse() => match direction {
+                Some(direction) => game.press(direction, clock.now_ms()),
+                None => {
+                    info!("GAME TASK: all button tasks are gone, nothing left to play");
+                    listening = false;
+                }
+            },
+            _ = delay.delay_ms(FRAME_PERIOD_MS).fuse() => {}
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use core::cell::RefCell;
+
+use crate::font::{GLYPH_HEIGHT, GLYPH_SPACING, glyph_pixel, glyph_width};
+use crate::framebuffer::FrameBuffer;
+use crate::runtime::Delay;
+
+pub const SCROLL_STEP_MS: u32 = 150;
+
+// Width of the rendered text in columns, including the gap after each glyph.
+pub fn text_width(text: &str) -> usize {
+    text.chars().map(|c| glyph_width(c) + GLYPH_SPACING).sum()
+}
+
+// Number of scroll steps until the text has entered on the right and left
+// the display on the left again.
+pub fn scroll_steps(text: &str, width: usize) -> usize {
+    width + text_width(text)
+}
+
+// Draws the text as seen at scroll position `step`: at step 0 the display is
+// still empty and the first column of the text is about to enter on the right.
+// Text is vertically centred on displays taller than a glyph.
+pub fn render_scroll<const W: usize, const H: usize>(
+    text: &str,
+    step: usize,
+    frame: &mut FrameBuffer<W, H>,
+) {
+    frame.clear();
+    let top = H.saturating_sub(GLYPH_HEIGHT) / 2;
+    let mut left = W as isize - step as isize;
+    for c in text.chars() {
+        let width = glyph_width(c);
+        for column in 0..width {
+            let x = left + column as isize;
+            if !(0..W as isize).contains(&x) {
+                continue;
+            }
+            for row in 0..GLYPH_HEIGHT.min(H) {
+                if glyph_pixel(c, column, row) {
+                    frame.set(x as usize, top + row, u8::MAX);
+                }
+            }
+        }
+        left += (width + GLYPH_SPACING) as isize;
+        if left >= W as isize {
+            break;
+        }
+    }
+}
+
+// Scrolls `text` across the shared frame buffer once; resolves when the last
+// column has left the display.
+pub async fn scroll_text<D: Delay, const W: usize, const H: usize>(
+    frame: &RefCell<FrameBuffer<W, H>>,
+    text: &str,
+    step_ms: u32,
+    delay: &mut D,
+) {
+    debug!("TEXT: scrolling {=str}", text);
+    for step in 0..=scroll_steps(text, W) {
+        render_scroll(text, step, &mut frame.borrow_mut());
+        delay.delay_ms(step_ms).await;
+    }
+}
+
+// micro:bit style text output on top of a frame buffer that some other task
+// (e.g. `matrix_task`) puts on the actual LEDs.
+pub struct TextDisplay<'a, D, const W: usize, const H: usize> {
+    frame: &'a RefCell<FrameBuffer<W, H>>,
+    delay: D,
+    step_ms: u32,
+}
+
+impl<'a, D: Delay, const W: usize, const H: usize> TextDisplay<'a, D, W, H> {
+    pub fn new(frame: &'a RefCell<FrameBuffer<W, H>>, delay: D) -> Self {
+        Self {
+            frame,
+            delay,
+            step_ms: SCROLL_STEP_MS,
+        }
+    }
+
+    pub fn set_scroll_speed(&mut self, step_ms: u32) {
+        self.step_ms = step_ms;
+    }
+
+    pub async fn scroll(&mut self, text: &str) {
+        scroll_text(self.frame, text, self.step_ms, &mut self.delay).await
+    }
+
+    // Shows a single character without scrolling, centred if there is room.
+    pub fn show(&mut self, c: char) {
+        let mut frame = self.frame.borrow_mut();
+        frame.clear();
+        let left = W.saturating_sub(glyph_width(c)) / 2;
+        let top = H.saturating_sub(GLYPH_HEIGHT) / 2;
+        for row in 0..GLYPH_HEIGHT.min(H) {
+            for column in 0..glyph_width(c).min(W - left) {
+                if glyph_pixel(c, column, row) {
+                    frame.set(left + column, top + row, u8::MAX);
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.frame.borrow_mut().clear();
+    }
+}
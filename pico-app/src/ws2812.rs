@@ -0,0 +1,211 @@
+// WS2812 ("NeoPixel") strips: every pixel takes 24 bits, green first, most
+// significant bit first, sent as one 800 kHz self-clocked waveform on a single
+// pin. A pause of more than RESET_US latches the data into the pixels.
+#![allow(async_fn_in_trait)]
+
+use core::cell::RefCell;
+use core::convert::Infallible;
+
+use embedded_hal::digital::{ErrorType, OutputPin, StatefulOutputPin};
+
+use crate::framebuffer::Display;
+use crate::pwm::gamma;
+
+pub const BIT_RATE_HZ: u32 = 800_000;
+pub const RESET_US: u32 = 280;
+
+// Every bit is T1 + T2 + T3 state machine cycles long: low for T3, high for
+// T1, then high (one) or low (zero) for T2.
+pub const T1: u32 = 2;
+pub const T2: u32 = 5;
+pub const T3: u32 = 3;
+pub const CYCLES_PER_BIT: u32 = T1 + T2 + T3;
+pub const BITS_PER_PIXEL: u32 = 24;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb::new(0, 0, 0);
+    pub const WHITE: Rgb = Rgb::new(255, 255, 255);
+    pub const RED: Rgb = Rgb::new(255, 0, 0);
+    pub const GREEN: Rgb = Rgb::new(0, 255, 0);
+    pub const BLUE: Rgb = Rgb::new(0, 0, 255);
+    pub const YELLOW: Rgb = Rgb::new(255, 255, 0);
+    pub const CYAN: Rgb = Rgb::new(0, 255, 255);
+    pub const MAGENTA: Rgb = Rgb::new(255, 0, 255);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+
+    // Hue, saturation and value all span the full 0..=255 range, a hue of 0
+    // (and 256) is red, 85 green and 170 blue.
+    pub fn hsv(hue: u8, saturation: u8, value: u8) -> Self {
+        let region = hue / 43;
+        let remainder = (hue - region * 43) as u32 * 6;
+        let v = value as u32;
+        let s = saturation as u32;
+        let p = (v * (255 - s) / 255) as u8;
+        let q = (v * (255 - s * remainder / 255) / 255) as u8;
+        let t = (v * (255 - s * (255 - remainder) / 255) / 255) as u8;
+        match region {
+            0 => Rgb::new(value, t, p),
+            1 => Rgb::new(q, value, p),
+            2 => Rgb::new(p, value, t),
+            3 => Rgb::new(p, q, value),
+            4 => Rgb::new(t, p, value),
+            _ => Rgb::new(value, p, q),
+        }
+    }
+
+    // Dims the colour to `level` of its brightness.
+    pub fn scale(self, level: u8) -> Self {
+        let scale = |c: u8| (c as u16 * level as u16 / u8::MAX as u16) as u8;
+        Rgb::new(scale(self.r), scale(self.g), scale(self.b))
+    }
+
+    // Same curve as the PWM LEDs, so a level looks alike on both.
+    pub fn gamma(self) -> Self {
+        let correct = |c: u8| (gamma(c) >> 8) as u8;
+        Rgb::new(correct(self.r), correct(self.g), correct(self.b))
+    }
+}
+
+// The word the state machine shifts out, left aligned: it pulls 24 bits per
+// pixel and shifts them out to the left.
+pub fn encode(color: Rgb) -> u32 {
+    ((color.g as u32) << 24) | ((color.r as u32) << 16) | ((color.b as u32) << 8)
+}
+
+// Length of the high and low phase of a single bit in state machine cycles.
+pub fn bit_timing(bit: bool) -> (u32, u32) {
+    if bit { (T1 + T2, T3) } else { (T1, T2 + T3) }
+}
+
+// Clock divider for the state machine as 16.8 fixed point integer and
+// fractional part, rounded to the nearest 1/256.
+pub fn clock_divider(sys_clock_hz: u32) -> (u16, u8) {
+    let cycles_hz = (BIT_RATE_HZ * CYCLES_PER_BIT) as u64;
+    let divider = ((sys_clock_hz as u64) * 256 + cycles_hz / 2) / cycles_hz;
+    ((divider >> 8) as u16, (divider & 0xff) as u8)
+}
+
+// Time it takes to shift out `pixels` pixels, without the reset pause.
+pub fn frame_time_us(pixels: usize) -> u32 {
+    (pixels as u32 * BITS_PER_PIXEL * 1_000_000).div_ceil(BIT_RATE_HZ)
+}
+
+// Pushes encoded pixels to the strip, resolves once the data is latched.
+pub trait StripWriter<const N: usize> {
+    async fn write(&mut self, words: &[u32; N]);
+}
+
+// RGB frame buffer of a strip with `N` pixels. Brightness levels written
+// through `Display` or the pixel pins are shown in `color`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColorStrip<const N: usize> {
+    pixels: [Rgb; N],
+    color: Rgb,
+}
+
+impl<const N: usize> ColorStrip<N> {
+    pub const fn new(color: Rgb) -> Self {
+        Self {
+            pixels: [Rgb::BLACK; N],
+            color,
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Rgb {
+        self.pixels[index]
+    }
+
+    pub fn set(&mut self, index: usize, color: Rgb) {
+        self.pixels[index] = color;
+    }
+
+    pub fn fill(&mut self, color: Rgb) {
+        self.pixels = [color; N];
+    }
+
+    pub fn clear(&mut self) {
+        self.fill(Rgb::BLACK);
+    }
+
+    pub fn color(&self) -> Rgb {
+        self.color
+    }
+
+    pub fn set_color(&mut self, color: Rgb) {
+        self.color = color;
+    }
+
+    pub fn encode(&self) -> [u32; N] {
+        self.pixels.map(|pixel| encode(pixel.gamma()))
+    }
+}
+
+impl<const N: usize> Default for ColorStrip<N> {
+    fn default() -> Self {
+        Self::new(Rgb::WHITE)
+    }
+}
+
+impl<const N: usize> Display<N, 1> for ColorStrip<N> {
+    fn write_pixel(&mut self, x: usize, _y: usize, level: u8) {
+        self.pixels[x] = self.color.scale(level);
+    }
+}
+
+// Lets a task draw into a strip that another task (see `strip_task`) pushes
+// out to the hardware.
+impl<const N: usize> Display<N, 1> for &RefCell<ColorStrip<N>> {
+    fn write_pixel(&mut self, x: usize, y: usize, level: u8) {
+        self.borrow_mut().write_pixel(x, y, level);
+    }
+}
+
+// A single strip pixel posing as an output pin, so a `LedRow` can drive the
+// strip like a row of discrete LEDs.
+pub struct StripPixel<'a, const N: usize> {
+    strip: &'a RefCell<ColorStrip<N>>,
+    index: usize,
+}
+
+pub fn pixel_pins<const N: usize>(strip: &RefCell<ColorStrip<N>>) -> [StripPixel<'_, N>; N] {
+    core::array::from_fn(|index| StripPixel { strip, index })
+}
+
+impl<const N: usize> ErrorType for StripPixel<'_, N> {
+    type Error = Infallible;
+}
+
+impl<const N: usize> OutputPin for StripPixel<'_, N> {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.strip.borrow_mut().set(self.index, Rgb::BLACK);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        let mut strip = self.strip.borrow_mut();
+        let color = strip.color();
+        strip.set(self.index, color);
+        Ok(())
+    }
+}
+
+impl<const N: usize> StatefulOutputPin for StripPixel<'_, N> {
+    fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.strip.borrow().get(self.index) != Rgb::BLACK)
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.strip.borrow().get(self.index) == Rgb::BLACK)
+    }
+}
@@ -0,0 +1,153 @@
+use pico_app::ButtonDirection::{Left, Right};
+use pico_app::animation::{
+    AUTO_CYCLE_FRAMES, Animation, Animator, BarGraph, CROSSFADE_FRAMES, Crossfade, Pattern,
+    Progress, Scanner,
+};
+use pico_app::gesture::{DOUBLE_PRESS_WINDOW_MS, Gesture, GestureDetector};
+
+#[test]
+fn scanner_bounces_between_the_ends() {
+    let scanner = Scanner {
+        frames_per_step: 1,
+        fade_per_led: 100,
+    };
+    let mut frame = [0; 5];
+    let positions: Vec<usize> = (0..10)
+        .map(|tick| {
+            scanner.render(tick, &mut frame);
+            frame.iter().position(|&level| level == 255).unwrap()
+        })
+        .collect();
+    assert_eq!(positions, [0, 1, 2, 3, 4, 3, 2, 1, 0, 1]);
+    // the tail fades behind the lit LED
+    scanner.render(2, &mut frame);
+    assert_eq!(frame, [55, 155, 255, 155, 55]);
+}
+
+#[test]
+fn bar_graph_fills_up_to_the_level() {
+    let mut frame = [0; 5];
+    BarGraph { level: 255 }.render(0, &mut frame);
+    assert_eq!(frame, [255; 5]);
+    BarGraph { level: 127 }.render(0, &mut frame);
+    assert_eq!(frame, [255, 255, 125, 0, 0]);
+    BarGraph { level: 0 }.render(0, &mut frame);
+    assert_eq!(frame, [0; 5]);
+}
+
+#[test]
+fn progress_lights_whole_leds() {
+    let mut frame = [0; 5];
+    Progress {
+        percent: 40,
+        frames_per_blink: 1,
+    }
+    .render(0, &mut frame);
+    assert_eq!(frame, [255, 255, 255, 0, 0]);
+    Progress {
+        percent: 100,
+        frames_per_blink: 1,
+    }
+    .render(1, &mut frame);
+    assert_eq!(frame, [255; 5]);
+}
+
+#[test]
+fn crossfade_mixes_by_elapsed_frames() {
+    let fade = Crossfade {
+        from: BarGraph { level: 0 },
+        to: BarGraph { level: 255 },
+        start: 10,
+        frames: 10,
+    };
+    let mut frame = [0; 5];
+    fade.render(5, &mut frame);
+    assert_eq!(frame, [0; 5]);
+    fade.render(15, &mut frame);
+    assert_eq!(frame, [127; 5]);
+    fade.render(20, &mut frame);
+    assert_eq!(frame, [255; 5]);
+}
+
+#[test]
+fn animator_fades_into_the_next_animation() {
+    let mut animator = Animator::new(Animation::BarGraph);
+    let mut frame = [0; 5];
+    animator.next_frame(&mut frame);
+    animator.handle(Gesture::Press(Right));
+    assert_eq!(animator.current(), Animation::Chase);
+    animator.handle(Gesture::Press(Left));
+    animator.handle(Gesture::Press(Left));
+    assert_eq!(animator.current(), Animation::Scanner);
+
+    // after the crossfade only the new animation is left
+    for _ in 0..CROSSFADE_FRAMES {
+        animator.next_frame(&mut frame);
+    }
+    let tick = 1 + CROSSFADE_FRAMES;
+    let mut expected = [0; 5];
+    Animation::Scanner.render(tick, &mut expected);
+    animator.next_frame(&mut frame);
+    assert_eq!(frame, expected);
+}
+
+#[test]
+fn double_press_toggles_auto_cycling() {
+    let mut animator = Animator::new(Animation::Scanner);
+    let mut frame = [0; 5];
+    animator.handle(Gesture::DoublePress(Left));
+    for _ in 0..AUTO_CYCLE_FRAMES {
+        animator.next_frame(&mut frame);
+    }
+    assert_eq!(animator.current(), Animation::Scanner);
+    animator.next_frame(&mut frame);
+    assert_eq!(animator.current(), Animation::BarGraph);
+    for _ in 0..AUTO_CYCLE_FRAMES {
+        animator.next_frame(&mut frame);
+    }
+    assert_eq!(animator.current(), Animation::Chase);
+
+    animator.handle(Gesture::DoublePress(Right));
+    for _ in 0..2 * AUTO_CYCLE_FRAMES {
+        animator.next_frame(&mut frame);
+    }
+    assert_eq!(animator.current(), Animation::Chase);
+}
+
+#[test]
+fn second_press_within_the_window_is_a_double_press() {
+    let mut gestures = GestureDetector::new(DOUBLE_PRESS_WINDOW_MS);
+    assert_eq!(gestures.press(Left, 0), None);
+    assert_eq!(gestures.poll(DOUBLE_PRESS_WINDOW_MS), None);
+    assert_eq!(
+        gestures.press(Left, DOUBLE_PRESS_WINDOW_MS),
+        Some(Gesture::DoublePress(Left))
+    );
+    // nothing is left pending
+    assert_eq!(gestures.poll(10 * DOUBLE_PRESS_WINDOW_MS), None);
+}
+
+#[test]
+fn single_press_is_reported_once_the_window_passed() {
+    let mut gestures = GestureDetector::new(DOUBLE_PRESS_WINDOW_MS);
+    assert_eq!(gestures.press(Left, 1000), None);
+    assert_eq!(gestures.poll(1000 + DOUBLE_PRESS_WINDOW_MS), None);
+    assert_eq!(
+        gestures.poll(1001 + DOUBLE_PRESS_WINDOW_MS),
+        Some(Gesture::Press(Left))
+    );
+    assert_eq!(gestures.poll(2000), None);
+
+    // too late for a double press: two single presses
+    assert_eq!(gestures.press(Right, 2000), None);
+    assert_eq!(
+        gestures.press(Right, 2001 + DOUBLE_PRESS_WINDOW_MS),
+        Some(Gesture::Press(Right))
+    );
+    // a press of the other button ends the window early
+    assert_eq!(gestures.press(Left, 2400), Some(Gesture::Press(Right)));
+    assert_eq!(
+        gestures.poll(2400 + DOUBLE_PRESS_WINDOW_MS + 1),
+        Some(Gesture::Press(Left))
+    );
+}
@@ -0,0 +1,149 @@
+mod common;
+
+use std::task::Poll;
+
+use futures::StreamExt;
+use futures::executor::block_on;
+use pico_app::channel::Channel;
+
+use common::{WakeCounter, poll};
+
+#[test]
+fn receives_in_the_order_sent() {
+    let channel: Channel<_> = Channel::new();
+    let sender = channel.get_sender();
+    let mut receiver = channel.get_receiver();
+    for item in 1..=3 {
+        block_on(sender.send(item)).unwrap();
+    }
+    assert_eq!(channel.len(), 3);
+    for item in 1..=3 {
+        assert_eq!(block_on(receiver.receive()), Some(item));
+    }
+    assert!(channel.is_empty());
+}
+
+#[test]
+fn full_queue_makes_senders_wait() {
+    let channel: Channel<_, 2> = Channel::new();
+    let sender = channel.get_sender();
+    let other = sender.clone();
+    let mut receiver = channel.get_receiver();
+    block_on(sender.send(1)).unwrap();
+    block_on(sender.send(2)).unwrap();
+
+    let counter = WakeCounter::new();
+    let mut third = Box::pin(sender.send(3));
+    let mut fourth = Box::pin(other.send(4));
+    assert_eq!(poll(&mut third, &counter.waker()), Poll::Pending);
+    assert_eq!(poll(&mut fourth, &counter.waker()), Poll::Pending);
+    assert_eq!(counter.count(), 0);
+
+    // nothing was overwritten
+    assert_eq!(block_on(receiver.receive()), Some(1));
+    assert!(counter.count() > 0);
+    assert_eq!(poll(&mut third, &counter.waker()), Poll::Ready(Ok(())));
+    assert_eq!(poll(&mut fourth, &counter.waker()), Poll::Pending);
+    assert_eq!(block_on(receiver.receive()), Some(2));
+    assert_eq!(poll(&mut fourth, &counter.waker()), Poll::Ready(Ok(())));
+    assert_eq!(block_on(receiver.receive()), Some(3));
+    assert_eq!(block_on(receiver.receive()), Some(4));
+}
+
+#[test]
+fn last_sender_drop_ends_the_receiver_after_the_queue() {
+    let channel: Channel<_> = Channel::new();
+    let first = channel.get_sender();
+    let second = first.clone();
+    let mut receiver = channel.get_receiver();
+
+    let counter = WakeCounter::new();
+    let mut receive = Box::pin(receiver.receive());
+    assert_eq!(poll(&mut receive, &counter.waker()), Poll::Pending);
+    drop(first);
+    assert_eq!(counter.count(), 0);
+    assert_eq!(poll(&mut receive, &counter.waker()), Poll::Pending);
+
+    block_on(second.send('x')).unwrap();
+    block_on(second.send('y')).unwrap();
+    drop(second);
+    assert!(counter.count() > 0);
+    assert_eq!(poll(&mut receive, &counter.waker()), Poll::Ready(Some('x')));
+    drop(receive);
+    assert_eq!(block_on(receiver.receive()), Some('y'));
+    assert_eq!(block_on(receiver.receive()), None);
+}
+
+#[test]
+fn receiver_streams_until_the_senders_are_gone() {
+    let channel: Channel<_> = Channel::new();
+    let sender = channel.get_sender();
+    let mut receiver = channel.get_receiver();
+    block_on(async move {
+        for item in 1..=3 {
+            sender.send(item).await.unwrap();
+        }
+        assert_eq!(receiver.next().await, Some(1));
+        drop(sender);
+        let rest: Vec<_> = receiver.map(|item| item * 10).collect().await;
+        assert_eq!(rest, [20, 30]);
+    });
+}
+
+#[test]
+fn dropped_receiver_closes_the_channel() {
+    let channel: Channel<_, 1> = Channel::new();
+    let sender = channel.get_sender();
+    let receiver = channel.get_receiver();
+    block_on(sender.send(1)).unwrap();
+
+    let counter = WakeCounter::new();
+    let mut waiting = Box::pin(sender.send(2));
+    assert_eq!(poll(&mut waiting, &counter.waker()), Poll::Pending);
+    drop(receiver);
+    // the waiting sender gets its item back too
+    assert!(counter.count() > 0);
+    assert_eq!(poll(&mut waiting, &counter.waker()), Poll::Ready(Err(2)));
+    assert_eq!(block_on(sender.send(3)), Err(3));
+}
+
+#[test]
+fn closed_channel_still_hands_out_the_queue() {
+    let channel: Channel<_, 1> = Channel::new();
+    let sender = channel.get_sender();
+    let mut receiver = channel.get_receiver();
+    block_on(sender.send(1)).unwrap();
+
+    let counter = WakeCounter::new();
+    let mut waiting = Box::pin(sender.send(2));
+    assert_eq!(poll(&mut waiting, &counter.waker()), Poll::Pending);
+    receiver.close();
+    assert!(counter.count() > 0);
+    assert_eq!(poll(&mut waiting, &counter.waker()), Poll::Ready(Err(2)));
+    assert_eq!(block_on(sender.send(3)), Err(3));
+
+    // the senders are still there, the item queued before is not lost
+    assert_eq!(block_on(receiver.receive()), Some(1));
+    assert_eq!(block_on(receiver.receive()), None);
+}
+
+#[test]
+fn receiver_waits_for_the_first_sender() {
+    let channel: Channel<_> = Channel::new();
+    let mut receiver = channel.get_receiver();
+
+    let counter = WakeCounter::new();
+    let mut receive = Box::pin(receiver.receive());
+    assert_eq!(poll(&mut receive, &counter.waker()), Poll::Pending);
+
+    let sender = channel.get_sender();
+    block_on(sender.send(7)).unwrap();
+    assert!(counter.count() > 0);
+    assert_eq!(poll(&mut receive, &counter.waker()), Poll::Ready(Some(7)));
+    drop(receive);
+
+    drop(sender);
+    assert_eq!(block_on(receiver.receive()), None);
+    // a sender coming late finds the channel closed
+    assert_eq!(block_on(channel.get_sender().send(8)), Err(8));
+}
@@ -0,0 +1,155 @@
+// Helpers to drive futures by hand and fakes of the runtime, shared by the
+// tests
+#![allow(dead_code)] // not every test uses every helper
+
+use std::cell::{Cell, RefCell};
+use std::future::{Future, poll_fn};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll, Wake, Waker};
+
+use pico_app::runtime::{Clock, Delay, Input};
+
+// A waker that counts how often it was woken.
+pub struct WakeCounter(AtomicUsize);
+
+impl WakeCounter {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self(AtomicUsize::new(0)))
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn waker(self: &Arc<Self>) -> Waker {
+        Waker::from(self.clone())
+    }
+}
+
+impl Wake for WakeCounter {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref()
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub fn poll<F: Future + Unpin>(future: &mut F, waker: &Waker) -> Poll<F::Output> {
+    Pin::new(future).poll(&mut Context::from_waker(waker))
+}
+
+// Polls `future` until it completes or waits for something only the test
+// can do, e.g. advance the time or change a pin.
+pub fn run_until_stalled<F: Future>(mut future: Pin<&mut F>) -> Poll<F::Output> {
+    let counter = WakeCounter::new();
+    let waker = counter.waker();
+    loop {
+        let woken = counter.count();
+        if let Poll::Ready(output) = future.as_mut().poll(&mut Context::from_waker(&waker)) {
+            return Poll::Ready(output);
+        }
+        if counter.count() == woken {
+            return Poll::Pending;
+        }
+    }
+}
+
+// Milliseconds that only pass when the test says so, for `Delay` and `Clock`.
+#[derive(Clone, Default)]
+pub struct FakeTime(Rc<TimeState>);
+
+#[derive(Default)]
+struct TimeState {
+    now_ms: Cell<u32>,
+    wakers: RefCell<Vec<Waker>>,
+}
+
+impl FakeTime {
+    pub fn now(&self) -> u32 {
+        self.0.now_ms.get()
+    }
+
+    pub fn advance(&self, millis: u32) {
+        self.0.now_ms.set(self.now() + millis);
+        for waker in self.0.wakers.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Delay for FakeTime {
+    async fn delay_ms(&mut self, millis: u32) {
+        let end = self.now() + millis;
+        poll_fn(|cx| {
+            if self.now() >= end {
+                Poll::Ready(())
+            } else {
+                self.0.wakers.borrow_mut().push(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+impl Clock for FakeTime {
+    fn now_ms(&self) -> u32 {
+        self.now()
+    }
+}
+
+// An input pin the test sets, e.g. a button with a pull-up.
+#[derive(Clone)]
+pub struct FakePin(Rc<PinLevel>);
+
+struct PinLevel {
+    high: Cell<bool>,
+    waker: Cell<Option<Waker>>,
+}
+
+impl FakePin {
+    pub fn new(high: bool) -> Self {
+        Self(Rc::new(PinLevel {
+            high: Cell::new(high),
+            waker: Cell::new(None),
+        }))
+    }
+
+    pub fn set_high(&self, high: bool) {
+        self.0.high.set(high);
+        if let Some(waker) = self.0.waker.take() {
+            waker.wake();
+        }
+    }
+
+    async fn wait_for(&self, high: bool) {
+        poll_fn(|cx| {
+            if self.0.high.get() == high {
+                Poll::Ready(())
+            } else {
+                self.0.waker.set(Some(cx.waker().clone()));
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+impl Input for FakePin {
+    async fn wait_for_low(&mut self) {
+        self.wait_for(false).await
+    }
+
+    async fn wait_for_high(&mut self) {
+        self.wait_for(true).await
+    }
+
+    fn is_high(&mut self) -> bool {
+        self.0.high.get()
+    }
+}
@@ -0,0 +1,317 @@
+mod common;
+
+use std::fmt::Write;
+
+use futures::executor::block_on;
+use futures::join;
+use pico_app::console::{
+    Command, LineBuffer, LineEvent, ParseError, Reply, Stats, SystemInfo, TaskInfo, execute, parse,
+    run_line,
+};
+use pico_app::led::LedControl;
+use pico_app::oneshot::Oneshot;
+
+use common::{WakeCounter, poll};
+
+struct NoInfo;
+
+impl SystemInfo for NoInfo {
+    fn stats(&self) -> Stats {
+        Stats::default()
+    }
+
+    fn task(&self, _id: usize) -> Option<TaskInfo> {
+        None
+    }
+}
+
+// An executor with three tasks, the last one done.
+struct FakeInfo {
+    idle_count: Option<u32>,
+}
+
+impl SystemInfo for FakeInfo {
+    fn stats(&self) -> Stats {
+        Stats {
+            uptime_ms: 1234,
+            idle_count: self.idle_count,
+            rx_bytes: 56,
+            tx_bytes: 789,
+            rx_dropped: 1,
+        }
+    }
+
+    fn task(&self, id: usize) -> Option<TaskInfo> {
+        (id < 3).then_some(TaskInfo {
+            polls: 10 * id as u32,
+            finished: id == 2,
+        })
+    }
+}
+
+const INFO: FakeInfo = FakeInfo {
+    idle_count: Some(7),
+};
+
+fn run<I: SystemInfo>(line: &str, control: &LedControl<'_>, info: &I) -> String {
+    let mut reply = Reply::<256>::new();
+    block_on(run_line(line, control, info, &mut reply)).unwrap();
+    String::from_utf8(reply.as_bytes().to_vec()).unwrap()
+}
+
+fn push_all<const N: usize>(line: &mut LineBuffer<N>, bytes: &[u8]) -> Vec<LineEvent> {
+    bytes.iter().map(|&byte| line.push(byte)).collect()
+}
+
+#[test]
+fn commands_parse_with_any_spacing() {
+    assert_eq!(parse("help"), Ok(Command::Help));
+    assert_eq!(parse("?"), Ok(Command::Help));
+    assert_eq!(parse("led"), Ok(Command::ActiveLed));
+    assert_eq!(parse("led 3"), Ok(Command::Led(3)));
+    assert_eq!(parse("  blink \t 250  "), Ok(Command::Blink(250)));
+    assert_eq!(parse("stats"), Ok(Command::Stats));
+    assert_eq!(parse("tasks"), Ok(Command::Tasks));
+}
+
+#[test]
+fn bad_lines_tell_what_is_wrong() {
+    assert_eq!(parse(""), Err(ParseError::Empty));
+    assert_eq!(parse("   "), Err(ParseError::Empty));
+    assert_eq!(parse("fade"), Err(ParseError::UnknownCommand));
+    assert_eq!(parse("LED 3"), Err(ParseError::UnknownCommand));
+    assert_eq!(parse("blink"), Err(ParseError::MissingArgument));
+    assert_eq!(parse("led x"), Err(ParseError::InvalidArgument));
+    assert_eq!(parse("led -1"), Err(ParseError::InvalidArgument));
+    assert_eq!(parse("blink fast"), Err(ParseError::InvalidArgument));
+    // outside of MIN_BLINK_MS..=MAX_BLINK_MS
+    assert_eq!(parse("blink 9"), Err(ParseError::InvalidArgument));
+    assert_eq!(parse("blink 10"), Ok(Command::Blink(10)));
+    assert_eq!(parse("blink 10000"), Ok(Command::Blink(10_000)));
+    assert_eq!(parse("blink 10001"), Err(ParseError::InvalidArgument));
+    assert_eq!(parse("led 1 2"), Err(ParseError::TooManyArguments));
+    assert_eq!(parse("stats now"), Err(ParseError::TooManyArguments));
+}
+
+#[test]
+fn cr_lf_and_crlf_each_end_one_line() {
+    use LineEvent::{Done, Echo, Ignored};
+    let mut line = LineBuffer::<8>::new();
+    assert_eq!(push_all(&mut line, b"a\r"), [Echo(b'a'), Done]);
+    line.clear();
+    assert_eq!(push_all(&mut line, b"b\n"), [Echo(b'b'), Done]);
+    line.clear();
+    // the LF of a CR LF doesn't end a second, empty line
+    assert_eq!(push_all(&mut line, b"c\r\n"), [Echo(b'c'), Done, Ignored]);
+    line.clear();
+    // but two LFs or two CRs do
+    assert_eq!(push_all(&mut line, b"\n\n\r\r"), [Done; 4]);
+    // LF CR is two line ends as well
+    assert_eq!(push_all(&mut line, b"d\n\r"), [Echo(b'd'), Done, Done]);
+}
+
+#[test]
+fn backspace_and_delete_erase_the_last_character() {
+    let mut line = LineBuffer::<8>::new();
+    let events = push_all(&mut line, b"lex\x08d\x7f\x7fed");
+    assert_eq!(events[3], LineEvent::Erase);
+    assert_eq!(events[5..7], [LineEvent::Erase; 2]);
+    assert_eq!(line.as_str(), "led");
+    // nothing left to erase
+    line.clear();
+    assert_eq!(line.push(0x08), LineEvent::Ignored);
+    assert_eq!(line.push(0x7f), LineEvent::Ignored);
+    assert_eq!(line.as_str(), "");
+}
+
+#[test]
+fn control_characters_are_ignored() {
+    let mut line = LineBuffer::<8>::new();
+    let events = push_all(&mut line, b"l\x1b\te\x00d\xc3");
+    assert_eq!(
+        events,
+        [
+            LineEvent::Echo(b'l'),
+            LineEvent::Ignored,
+            LineEvent::Ignored,
+            LineEvent::Echo(b'e'),
+            LineEvent::Ignored,
+            LineEvent::Echo(b'd'),
+            LineEvent::Ignored,
+        ]
+    );
+    assert_eq!(line.as_str(), "led");
+}
+
+#[test]
+fn overlong_lines_are_cut_off() {
+    let mut line = LineBuffer::<4>::new();
+    let events = push_all(&mut line, b"led 12\r");
+    assert_eq!(events[3], LineEvent::Echo(b' '));
+    assert_eq!(events[4..6], [LineEvent::Ignored; 2]);
+    assert_eq!(events[6], LineEvent::Done);
+    assert_eq!(line.as_str(), "led ");
+    // room again after erasing
+    assert_eq!(line.push(0x7f), LineEvent::Erase);
+    assert_eq!(line.push(b'!'), LineEvent::Echo(b'!'));
+    assert_eq!(line.as_str(), "led!");
+}
+
+#[test]
+fn reply_keeps_what_fits() {
+    let mut reply = Reply::<8>::new();
+    assert_eq!(write!(reply, "led {}\r\n", 3), Ok(()));
+    assert_eq!(reply.as_bytes(), b"led 3\r\n");
+    assert!(write!(reply, "ok\r\n").is_err());
+    assert_eq!(reply.as_bytes(), b"led 3\r\no");
+    assert!(reply.write_str("").is_ok());
+    assert!(reply.write_str("x").is_err());
+
+    reply.clear();
+    assert_eq!(reply.as_bytes(), b"");
+    assert_eq!(reply.write_str("12345678"), Ok(()));
+    assert_eq!(reply.as_bytes(), b"12345678");
+}
+
+#[test]
+fn changes_without_an_led_task_are_refused() {
+    let slot = Oneshot::new();
+    let control = LedControl::new(4, 500, &slot);
+    for command in [Command::Led(1), Command::Blink(250)] {
+        let mut reply = Reply::<64>::new();
+        block_on(execute(command, &control, &NoInfo, &mut reply)).unwrap();
+        assert_eq!(reply.as_bytes(), b"error: no LED task in this mode\r\n");
+    }
+    assert_eq!(control.take_led(), None);
+    assert_eq!(control.blink_ms(), 500);
+
+    control.attach();
+    let mut reply = Reply::<64>::new();
+    block_on(execute(Command::Led(1), &control, &NoInfo, &mut reply)).unwrap();
+    assert_eq!(reply.as_bytes(), b"ok\r\n");
+    assert_eq!(control.take_led(), Some(1));
+}
+
+#[test]
+fn run_line_answers_errors_and_empty_lines() {
+    let slot = Oneshot::new();
+    let control = LedControl::new(4, 500, &slot);
+    assert_eq!(run("", &control, &INFO), "");
+    assert_eq!(run("  ", &control, &INFO), "");
+    assert_eq!(
+        run("fade", &control, &INFO),
+        "error: unknown command, try `help`\r\n"
+    );
+    assert_eq!(run("blink", &control, &INFO), "error: missing argument\r\n");
+    assert_eq!(run("led x", &control, &INFO), "error: invalid argument\r\n");
+    assert_eq!(
+        run("tasks 1", &control, &INFO),
+        "error: too many arguments\r\n"
+    );
+}
+
+#[test]
+fn run_line_changes_the_led_and_the_blink_period() {
+    let slot = Oneshot::new();
+    let control = LedControl::new(4, 500, &slot);
+    control.attach();
+    assert_eq!(run("led 3", &control, &INFO), "ok\r\n");
+    assert_eq!(control.take_led(), Some(3));
+    assert_eq!(
+        run("led 4", &control, &INFO),
+        "error: no LED 4, there are 4\r\n"
+    );
+    assert_eq!(control.take_led(), None);
+    assert_eq!(run("blink 250", &control, &INFO), "ok\r\n");
+    assert_eq!(control.blink_ms(), 250);
+}
+
+#[test]
+fn led_asks_the_led_task() {
+    let slot = Oneshot::new();
+    let control = LedControl::new(4, 500, &slot);
+    assert_eq!(
+        run("led", &control, &INFO),
+        "error: no LED task in this mode\r\n"
+    );
+
+    control.attach();
+    let led_task = async {
+        control.changed().await;
+        control.take_query().unwrap().send(2).unwrap();
+    };
+    let mut reply = Reply::<64>::new();
+    let (result, ()) = block_on(async {
+        join!(
+            execute(Command::ActiveLed, &control, &INFO, &mut reply),
+            led_task
+        )
+    });
+    result.unwrap();
+    assert_eq!(reply.as_bytes(), b"led 2\r\n");
+}
+
+#[test]
+fn led_from_both_consoles_at_once_is_busy_for_one() {
+    let slot = Oneshot::new();
+    let control = LedControl::new(4, 500, &slot);
+    control.attach();
+    let mut first = Reply::<64>::new();
+    let mut asking = Box::pin(execute(Command::ActiveLed, &control, &INFO, &mut first));
+    let counter = WakeCounter::new();
+    assert!(poll(&mut asking, &counter.waker()).is_pending());
+
+    assert_eq!(run("led", &control, &INFO), "error: busy, try again\r\n");
+
+    drop(control.take_query());
+    assert!(poll(&mut asking, &counter.waker()).is_ready());
+    drop(asking);
+    assert_eq!(first.as_bytes(), b"error: the LED task doesn't answer\r\n");
+}
+
+#[test]
+fn stats_leave_out_what_isnt_counted() {
+    let slot = Oneshot::new();
+    let control = LedControl::new(4, 500, &slot);
+    assert_eq!(
+        run("stats", &control, &INFO),
+        "uptime   1234 ms\r\n\
+         idle     7\r\n\
+         rx       56 bytes\r\n\
+         tx       789 bytes\r\n\
+         dropped  1 bytes\r\n"
+    );
+    let info = FakeInfo { idle_count: None };
+    assert!(!run("stats", &control, &info).contains("idle"));
+}
+
+#[test]
+fn tasks_are_listed_with_their_polls() {
+    let slot = Oneshot::new();
+    let control = LedControl::new(4, 500, &slot);
+    assert_eq!(
+        run("tasks", &control, &INFO),
+        "id  polls       state\r\n\
+         0   0           running\r\n\
+         1   10          running\r\n\
+         2   20          finished\r\n"
+    );
+    assert_eq!(
+        run("tasks", &control, &NoInfo),
+        "(this firmware keeps no task statistics)\r\n"
+    );
+}
+
+#[test]
+fn help_fits_the_row_and_a_short_reply_fails() {
+    let slot = Oneshot::new();
+    let control = LedControl::new(4, 500, &slot);
+    let help = run("help", &control, &INFO);
+    assert_eq!(help.lines().count(), 4);
+    assert!(help.starts_with("led [N]   show the active LED or select LED N, 0 to 3\r\n"));
+    assert!(help.contains("10..10000 ms"));
+
+    let mut reply = Reply::<16>::new();
+    assert!(block_on(execute(Command::Help, &control, &INFO, &mut reply)).is_err());
+    assert_eq!(reply.as_bytes(), b"led [N]   show t");
+}
@@ -0,0 +1,93 @@
+use pico_app::ButtonDirection::{self, Left, Right};
+use pico_app::encoder::{Acceleration, Detent, Encoder, QuadratureDecoder};
+
+// One full cycle each way, from and back to the rest position (both high).
+const RIGHT: [(bool, bool); 4] = [(false, true), (false, false), (true, false), (true, true)];
+const LEFT: [(bool, bool); 4] = [(true, false), (false, false), (false, true), (true, true)];
+
+fn feed(decoder: &mut QuadratureDecoder, samples: &[(bool, bool)]) -> Vec<ButtonDirection> {
+    samples
+        .iter()
+        .filter_map(|&(a, b)| decoder.update(a, b))
+        .collect()
+}
+
+#[test]
+fn every_single_step_counts_in_its_direction() {
+    // with quarter detents every transition is reported on its own
+    let cycle = [(true, true), (false, true), (false, false), (true, false)];
+    for start in 0..4 {
+        let (a, b) = cycle[start];
+        let mut decoder = QuadratureDecoder::new(Detent::Quarter, a, b);
+        let (a, b) = cycle[(start + 1) % 4];
+        assert_eq!(decoder.update(a, b), Some(Right));
+        let (a, b) = cycle[start];
+        assert_eq!(decoder.update(a, b), Some(Left));
+    }
+}
+
+#[test]
+fn skipped_and_repeated_states_dont_move() {
+    let mut decoder = QuadratureDecoder::new(Detent::Quarter, true, true);
+    // same state again, then a jump across two steps
+    assert_eq!(decoder.update(true, true), None);
+    assert_eq!(decoder.update(false, false), None);
+    assert_eq!(decoder.update(true, true), None);
+    assert_eq!(decoder.update(false, true), Some(Right));
+}
+
+#[test]
+fn full_detents_step_once_per_cycle() {
+    let mut decoder = QuadratureDecoder::new(Detent::Full, true, true);
+    assert_eq!(feed(&mut decoder, &RIGHT), [Right]);
+    assert_eq!(feed(&mut decoder, &LEFT), [Left]);
+    // bouncing on one contact never completes a detent
+    let bounce = [(true, false), (true, true), (true, false), (true, true)];
+    assert_eq!(feed(&mut decoder, &bounce), []);
+    // a missed edge still counts when more than half the cycle was seen
+    assert_eq!(
+        feed(&mut decoder, &[(true, false), (false, false), (true, true)]),
+        [Left]
+    );
+    // turning back half way is no step at all
+    assert_eq!(
+        feed(
+            &mut decoder,
+            &[(false, true), (false, false), (false, true), (true, true)]
+        ),
+        []
+    );
+}
+
+#[test]
+fn half_and_quarter_detents_step_more_often() {
+    let mut decoder = QuadratureDecoder::new(Detent::Half, true, true);
+    assert_eq!(feed(&mut decoder, &RIGHT), [Right, Right]);
+    assert_eq!(feed(&mut decoder, &LEFT), [Left, Left]);
+    let mut decoder = QuadratureDecoder::new(Detent::Quarter, true, true);
+    assert_eq!(feed(&mut decoder, &LEFT), [Left; 4]);
+}
+
+#[test]
+fn acceleration_grows_with_speed() {
+    let acceleration = Acceleration::default();
+    assert_eq!(acceleration.steps(200), 1);
+    assert_eq!(acceleration.steps(100), 1);
+    assert_eq!(acceleration.steps(60), 3);
+    assert_eq!(acceleration.steps(20), 5);
+    assert_eq!(acceleration.steps(0), 5);
+    assert_eq!(Acceleration::NONE.steps(0), 1);
+}
+
+#[test]
+fn encoder_accelerates_only_in_one_direction() {
+    let decoder = QuadratureDecoder::new(Detent::Full, true, true);
+    let mut encoder = Encoder::new(decoder, Acceleration::default());
+    let samples = RIGHT.iter().chain(&RIGHT).chain(&LEFT).chain(&LEFT);
+    let steps: Vec<_> = samples
+        .enumerate()
+        .filter_map(|(i, &(a, b))| encoder.update(a, b, i as u32 * 5))
+        .collect();
+    // 20 ms between the detents, turning back starts slow again
+    assert_eq!(steps, [(Right, 1), (Right, 5), (Left, 1), (Left, 5)]);
+}
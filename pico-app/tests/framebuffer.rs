@@ -0,0 +1,79 @@
+use pico_app::framebuffer::{Blend, Display, FrameBuffer, Refresher};
+
+// Records every pixel write.
+#[derive(Default)]
+struct Log(Vec<(usize, usize, u8)>);
+
+impl Display<3, 2> for Log {
+    fn write_pixel(&mut self, x: usize, y: usize, level: u8) {
+        self.0.push((x, y, level));
+    }
+}
+
+fn frame(rows: [[u8; 3]; 2]) -> FrameBuffer<3, 2> {
+    let mut frame = FrameBuffer::new();
+    for (y, row) in rows.iter().enumerate() {
+        *frame.row_mut(y) = *row;
+    }
+    frame
+}
+
+#[test]
+fn diff_lists_the_changed_pixels_in_order() {
+    let previous = frame([[0, 1, 2], [3, 4, 5]]);
+    let current = frame([[9, 1, 2], [3, 0, 7]]);
+    let changes: Vec<_> = current.diff(&previous).collect();
+    assert_eq!(changes, [(0, 0, 9), (1, 1, 0), (2, 1, 7)]);
+    assert_eq!(current.diff(&current).count(), 0);
+}
+
+#[test]
+fn refresh_writes_everything_once_then_only_changes() {
+    let mut refresher = Refresher::new();
+    let mut display = Log::default();
+    let mut current = FrameBuffer::<3, 2>::new();
+    refresher.refresh(&current, &mut display);
+    assert_eq!(display.0.len(), 6);
+
+    display.0.clear();
+    current.set(1, 1, 9);
+    refresher.refresh(&current, &mut display);
+    assert_eq!(display.0, [(1, 1, 9)]);
+
+    display.0.clear();
+    refresher.refresh(&current, &mut display);
+    assert!(display.0.is_empty());
+}
+
+#[test]
+fn every_blend_mode() {
+    let below = frame([[0, 100, 200], [255, 50, 0]]);
+    let layer = frame([[50, 50, 255], [128, 0, 0]]);
+    let blended = |blend| {
+        let mut frame = below;
+        frame.compose(&layer, blend);
+        [*frame.row(0), *frame.row(1)]
+    };
+    assert_eq!(blended(Blend::Lighten), [[50, 100, 255], [255, 50, 0]]);
+    assert_eq!(blended(Blend::Add), [[50, 150, 255], [255, 50, 0]]);
+    assert_eq!(blended(Blend::Mask), [[0, 19, 200], [128, 0, 0]]);
+    assert_eq!(blended(Blend::Over), [[50, 50, 255], [128, 50, 0]]);
+}
+
+#[test]
+fn layers_compose_in_order() {
+    let mut frame = FrameBuffer::<3, 2>::new();
+    frame.fill(100);
+    let mut dot = FrameBuffer::new();
+    dot.set(2, 0, 255);
+    let mut dim = FrameBuffer::new();
+    dim.fill(128);
+
+    frame.compose(&dot, Blend::Over);
+    frame.compose(&dim, Blend::Mask);
+    assert_eq!(*frame.row(0), [50, 50, 128]);
+    assert_eq!(*frame.row(1), [50; 3]);
+
+    frame.clear();
+    assert_eq!(frame, FrameBuffer::new());
+}
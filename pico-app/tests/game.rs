@@ -0,0 +1,167 @@
+use pico_app::ButtonDirection::{Left, Right};
+use pico_app::game::{
+    Game, PONG_MIN_STEP_MS, PONG_SPEEDUP_MS, PONG_START_STEP_MS, Pong, REACTION_MAX_WAIT_MS,
+    REACTION_MIN_WAIT_MS, ReactionTimer,
+};
+
+// Plays until the row lights up, returns when that was due.
+fn wait_for_go(game: &mut ReactionTimer, armed_ms: u32) -> u32 {
+    for now_ms in armed_ms..=armed_ms + REACTION_MAX_WAIT_MS {
+        Game::<5>::update(game, now_ms);
+        if game.is_go() {
+            return now_ms;
+        }
+    }
+    panic!("the row never lit up");
+}
+
+#[test]
+fn returned_ball_comes_back_faster() {
+    let mut pong = Pong::<5>::new();
+    assert!(pong.is_serving());
+    // only the server can serve
+    pong.press(Right, 0);
+    assert!(pong.is_serving());
+    pong.press(Left, 0);
+    pong.update(PONG_START_STEP_MS - 1);
+    assert_eq!(pong.ball(), 0);
+    pong.update(4 * PONG_START_STEP_MS);
+    assert_eq!(pong.ball(), 4);
+
+    pong.press(Right, 1250);
+    let step_ms = PONG_START_STEP_MS - PONG_SPEEDUP_MS;
+    assert_eq!(pong.step_ms(), step_ms);
+    pong.update(1250 + 3 * step_ms);
+    assert_eq!(pong.ball(), 1);
+    pong.update(1250 + 4 * step_ms);
+    assert_eq!(pong.ball(), 0);
+    pong.press(Left, 1250 + 4 * step_ms + 10);
+    assert_eq!(pong.step_ms(), step_ms - PONG_SPEEDUP_MS);
+    assert_eq!(pong.score(), (0, 0));
+}
+
+#[test]
+fn early_press_doesnt_return_the_ball() {
+    let mut pong = Pong::<5>::new();
+    pong.press(Left, 0);
+    pong.update(3 * PONG_START_STEP_MS);
+    assert_eq!(pong.ball(), 3);
+    pong.press(Right, 3 * PONG_START_STEP_MS + 10);
+    pong.update(4 * PONG_START_STEP_MS);
+    assert_eq!(pong.ball(), 4);
+    // the ball leaves the row: a point for the left player
+    pong.update(5 * PONG_START_STEP_MS);
+    assert_eq!(pong.score(), (1, 0));
+    assert!(pong.is_serving());
+    assert_eq!(pong.server(), Right);
+    assert_eq!(pong.ball(), 4);
+}
+
+#[test]
+fn late_press_serves_instead_of_returning() {
+    let mut pong = Pong::<5>::new();
+    pong.press(Left, 0);
+    pong.update(5 * PONG_START_STEP_MS);
+    assert_eq!(pong.score(), (1, 0));
+    // too late for the return, the ball waits for this serve instead
+    pong.press(Right, 5 * PONG_START_STEP_MS + 10);
+    assert!(!pong.is_serving());
+    assert_eq!(pong.step_ms(), PONG_START_STEP_MS);
+    pong.update(6 * PONG_START_STEP_MS + 10);
+    assert_eq!(pong.ball(), 3);
+
+    let mut frame = [0; 5];
+    pong.render(0, &mut frame);
+    assert_eq!(frame, [0, 0, 0, 255, 0]);
+}
+
+#[test]
+fn rallies_speed_up_to_the_limit() {
+    let mut pong = Pong::<2>::new();
+    let mut now_ms = 0;
+    pong.press(Left, now_ms);
+    for side in [Right, Left].into_iter().cycle().take(20) {
+        now_ms += pong.step_ms();
+        pong.update(now_ms);
+        pong.press(side, now_ms);
+    }
+    assert_eq!(pong.step_ms(), PONG_MIN_STEP_MS);
+    assert_eq!(pong.score(), (0, 0));
+}
+
+#[test]
+fn score_resets_after_the_winning_point() {
+    let mut pong = Pong::<5>::new();
+    let mut now_ms = 0;
+    for round in 1..=9 {
+        let server = pong.server();
+        pong.press(server, now_ms);
+        now_ms += 5 * PONG_START_STEP_MS;
+        pong.update(now_ms);
+        if round == 8 {
+            assert_eq!(pong.score(), (4, 4));
+        }
+    }
+    assert_eq!(pong.score(), (0, 0));
+}
+
+#[test]
+fn pong_survives_the_clock_wrapping() {
+    let mut pong = Pong::<3>::new();
+    let start_ms = u32::MAX - 100;
+    pong.press(Left, start_ms);
+    pong.update(start_ms.wrapping_add(PONG_START_STEP_MS));
+    assert_eq!(pong.ball(), 1);
+}
+
+#[test]
+fn reaction_is_measured_from_when_the_row_was_due() {
+    let mut game = ReactionTimer::new(7);
+    Game::<5>::press(&mut game, Left, 1000);
+    assert!(game.is_waiting());
+    Game::<5>::update(&mut game, 1000 + REACTION_MIN_WAIT_MS - 1);
+    assert!(game.is_waiting());
+    let go_ms = wait_for_go(&mut game, 1000);
+    let mut frame = [0; 5];
+    game.render(go_ms, &mut frame);
+    assert_eq!(frame, [255; 5]);
+
+    Game::<5>::press(&mut game, Right, go_ms + 120);
+    assert_eq!(game.last_ms(), Some(120));
+    assert_eq!(game.best_ms(), Some(120));
+    game.render(0, &mut frame);
+    // one LED per started REACTION_MS_PER_LED
+    assert_eq!(frame, [255, 255, 255, 0, 0]);
+
+    // noticed late, the round still counts from when it was due
+    Game::<5>::press(&mut game, Left, 20_000);
+    let go_ms = wait_for_go(&mut game, 20_000);
+    Game::<5>::update(&mut game, go_ms + 400);
+    Game::<5>::press(&mut game, Left, go_ms + 450);
+    assert_eq!(game.last_ms(), Some(450));
+    assert_eq!(game.best_ms(), Some(120));
+    game.render(0, &mut frame);
+    assert_eq!(frame, [255; 5]);
+}
+
+#[test]
+fn early_press_is_a_false_start() {
+    let mut game = ReactionTimer::new(7);
+    Game::<5>::press(&mut game, Left, 0);
+    let go_ms = wait_for_go(&mut game, 0);
+    Game::<5>::press(&mut game, Left, go_ms + 200);
+    assert_eq!(game.last_ms(), Some(200));
+
+    Game::<5>::press(&mut game, Left, 10_000);
+    Game::<5>::update(&mut game, 10_000 + REACTION_MIN_WAIT_MS - 1);
+    Game::<5>::press(&mut game, Left, 10_000 + REACTION_MIN_WAIT_MS - 1);
+    assert!(!game.is_waiting() && !game.is_go());
+    assert_eq!(game.last_ms(), None);
+    assert_eq!(game.best_ms(), Some(200));
+    // the first LED blinks after a false start
+    let mut frame = [0; 5];
+    game.render(0, &mut frame);
+    assert_eq!(frame, [255, 0, 0, 0, 0]);
+    game.render(500, &mut frame);
+    assert_eq!(frame, [0; 5]);
+}
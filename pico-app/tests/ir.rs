@@ -0,0 +1,208 @@
+use pico_app::input::Button;
+use pico_app::ir::{IR_KEYMAP, IrCommand, IrDecoder, NecDecoder, Protocol, Rc5Decoder};
+
+// Pulses as the receiver module delivers them: (mark, length in µs).
+type Pulses = Vec<(bool, u32)>;
+
+// The pause before a frame, long enough to reset any decoder.
+const IDLE: (bool, u32) = (false, 40_000);
+
+fn feed<D: IrDecoder>(decoder: &mut D, pulses: &[(bool, u32)]) -> Vec<IrCommand> {
+    pulses
+        .iter()
+        .filter_map(|&(mark, duration_us)| decoder.update(mark, duration_us))
+        .collect()
+}
+
+// A NEC frame sending the four bytes as they are, with the slightly off
+// timing of a real remote.
+fn nec_bytes(bytes: [u8; 4]) -> Pulses {
+    let data = u32::from_le_bytes(bytes);
+    let mut pulses = vec![IDLE, (true, 9050), (false, 4420)];
+    for bit in 0..32 {
+        let space = if data >> bit & 1 != 0 { 1660 } else { 540 };
+        pulses.extend([(true, 580), (false, space)]);
+    }
+    pulses.push((true, 600));
+    pulses
+}
+
+fn nec_frame(address: u8, command: u8) -> Pulses {
+    nec_bytes([address, !address, command, !command])
+}
+
+const NEC_REPEAT: [(bool, u32); 4] = [IDLE, (true, 9000), (false, 2250), (true, 560)];
+
+fn nec(address: u16, command: u8, repeat: bool) -> IrCommand {
+    IrCommand {
+        protocol: Protocol::Nec,
+        address,
+        command,
+        repeat,
+    }
+}
+
+fn rc5(address: u16, command: u8, repeat: bool) -> IrCommand {
+    IrCommand {
+        protocol: Protocol::Rc5,
+        address,
+        command,
+        repeat,
+    }
+}
+
+#[test]
+fn nec_frame_decodes_to_a_button() {
+    let mut decoder = NecDecoder::new();
+    let commands = feed(&mut decoder, &nec_frame(0x00, 0x44));
+    assert_eq!(commands, [nec(0x00, 0x44, false)]);
+    assert_eq!(IR_KEYMAP.button(&commands[0]), Some(Button::Left));
+    // a command the keymap doesn't know
+    let commands = feed(&mut decoder, &nec_frame(0x00, 0x07));
+    assert_eq!(IR_KEYMAP.button(&commands[0]), None);
+}
+
+#[test]
+fn nec_repeat_repeats_the_last_command() {
+    let mut decoder = NecDecoder::new();
+    // nothing to repeat yet
+    assert_eq!(feed(&mut decoder, &NEC_REPEAT), []);
+
+    feed(&mut decoder, &nec_frame(0x00, 0x40));
+    assert_eq!(feed(&mut decoder, &NEC_REPEAT), [nec(0x00, 0x40, true)]);
+    assert_eq!(feed(&mut decoder, &NEC_REPEAT), [nec(0x00, 0x40, true)]);
+}
+
+#[test]
+fn nec_extended_address_keeps_both_bytes() {
+    let mut decoder = NecDecoder::new();
+    let frame = nec_bytes([0x12, 0x34, 0x05, !0x05]);
+    assert_eq!(feed(&mut decoder, &frame), [nec(0x3412, 0x05, false)]);
+}
+
+#[test]
+fn nec_broken_frames_are_dropped_and_the_next_one_decodes() {
+    let mut decoder = NecDecoder::new();
+    // a bit space that is neither a zero nor a one
+    let mut frame = nec_frame(0x01, 0x02);
+    frame[10].1 = 1100;
+    assert_eq!(feed(&mut decoder, &frame), []);
+    // the command doesn't match its inverse
+    assert_eq!(
+        feed(&mut decoder, &nec_bytes([0x01, !0x01, 0x02, 0x02])),
+        []
+    );
+
+    // noise right before the frame
+    let mut pulses = vec![(true, 300), (false, 100)];
+    pulses.extend(nec_frame(0x10, 0x20));
+    assert_eq!(feed(&mut decoder, &pulses), [nec(0x10, 0x20, false)]);
+}
+
+// RC5 frames with toggle 0 and address 5, written out by hand. The first
+// half of the first start bit is a space and disappears in the idle line.
+const RC5_HEAD: [(bool, u32); 17] = [
+    (false, 50_000),
+    (true, 889),
+    (false, 889),
+    (true, 1778),
+    (false, 889),
+    (true, 889),
+    (false, 889),
+    (true, 889),
+    (false, 1778),
+    (true, 1778),
+    (false, 1778),
+    (true, 889),
+    (false, 889),
+    (true, 889),
+    (false, 889),
+    (true, 1778),
+    (false, 1778),
+];
+
+#[test]
+fn rc5_frame_ending_in_zero() {
+    // command 0x34, the trailing space merges into the pause after it
+    let mut pulses = RC5_HEAD.to_vec();
+    pulses.extend([(true, 1778), (false, 889), (true, 889)]);
+    // a little jitter, as from a real receiver
+    pulses[3].1 = 1700;
+    pulses[8].1 = 1850;
+    let mut decoder = Rc5Decoder::new();
+    assert_eq!(feed(&mut decoder, &pulses), [rc5(5, 0x34, false)]);
+}
+
+#[test]
+fn rc5_frame_ending_in_one() {
+    // command 0x35
+    let mut pulses = RC5_HEAD.to_vec();
+    pulses.extend([(true, 1778), (false, 1778), (true, 889)]);
+    pulses[1].1 = 820;
+    pulses[12].1 = 950;
+    let mut decoder = Rc5Decoder::new();
+    assert_eq!(feed(&mut decoder, &pulses), [rc5(5, 0x35, false)]);
+}
+
+// Manchester codes a frame, a one is a space followed by a mark.
+fn rc5_frame(toggle: bool, address: u8, command: u8) -> Pulses {
+    let bits = 1 << 13
+        | ((command & 0x40 == 0) as u32) << 12
+        | (toggle as u32) << 11
+        | (address as u32 & 0x1f) << 6
+        | (command as u32 & 0x3f);
+    let halves: Vec<bool> = (0..14)
+        .rev()
+        .flat_map(|bit| {
+            let one = bits >> bit & 1 != 0;
+            [!one, one]
+        })
+        .collect();
+
+    // run lengths, without the leading and trailing spaces
+    let mut pulses = vec![(false, 50_000)];
+    let mut i = 1;
+    while i < halves.len() {
+        let mark = halves[i];
+        let start = i;
+        while i < halves.len() && halves[i] == mark {
+            i += 1;
+        }
+        if i < halves.len() || mark {
+            pulses.push((mark, (i - start) as u32 * 889));
+        }
+    }
+    pulses
+}
+
+#[test]
+fn rc5_toggle_tells_repeats_from_new_presses() {
+    let mut decoder = Rc5Decoder::new();
+    for (toggle, repeat) in [(true, false), (true, true), (true, true), (false, false)] {
+        assert_eq!(
+            feed(&mut decoder, &rc5_frame(toggle, 1, 2)),
+            [rc5(1, 2, repeat)]
+        );
+    }
+}
+
+#[test]
+fn rc5x_commands_use_the_second_start_bit() {
+    let mut decoder = Rc5Decoder::new();
+    for (toggle, address, command) in [(false, 3, 0x40), (true, 31, 0x7f), (false, 0, 0x00)] {
+        let commands = feed(&mut decoder, &rc5_frame(toggle, address, command));
+        assert_eq!(commands, [rc5(address as u16, command, false)]);
+    }
+}
+
+#[test]
+fn rc5_broken_frame_is_dropped() {
+    let mut decoder = Rc5Decoder::new();
+    let mut frame = rc5_frame(false, 5, 0x35);
+    frame[6].1 = 1300;
+    assert_eq!(feed(&mut decoder, &frame), []);
+    assert_eq!(
+        feed(&mut decoder, &rc5_frame(false, 5, 0x35)),
+        [rc5(5, 0x35, false)]
+    );
+}
@@ -0,0 +1,96 @@
+use embedded_hal_mock::eh1::delay::NoopDelay;
+use embedded_hal_mock::eh1::digital::{Mock, State, Transaction};
+use pico_app::input::Action;
+use pico_app::keypad::{Debouncer, KEYPAD_DEBOUNCE_SCANS, KeyEvent, Keypad, is_ghosted};
+
+fn key(row: usize, column: usize, action: Action) -> KeyEvent {
+    KeyEvent {
+        row,
+        column,
+        action,
+    }
+}
+
+#[test]
+fn three_corners_of_a_rectangle_ghost() {
+    assert!(!is_ghosted(&[0, 0, 0]));
+    // a whole row, or a whole column, is fine
+    assert!(!is_ghosted(&[0b1111, 0, 0]));
+    assert!(!is_ghosted(&[0b0001, 0b0001, 0b0001]));
+    assert!(!is_ghosted(&[0b11, 0b01, 0]));
+    assert!(is_ghosted(&[0b11, 0b11, 0]));
+    assert!(is_ghosted(&[0b0110, 0, 0b1110]));
+}
+
+#[test]
+fn keys_change_after_enough_equal_scans() {
+    let mut debouncer = Debouncer::<2, 3>::new(3);
+    assert_eq!(debouncer.update(&[0b100, 0]).count(), 0);
+    assert_eq!(debouncer.update(&[0b100, 0]).count(), 0);
+    // a bounce starts the count over
+    assert_eq!(debouncer.update(&[0, 0]).count(), 0);
+    for _ in 0..2 {
+        assert_eq!(debouncer.update(&[0b100, 0b001]).count(), 0);
+    }
+    let events: Vec<_> = debouncer.update(&[0b100, 0b001]).collect();
+    assert_eq!(events, [key(0, 2, Action::Press), key(1, 0, Action::Press)]);
+    assert!(debouncer.is_pressed(0, 2));
+    assert!(debouncer.is_pressed(1, 0));
+    assert!(!debouncer.is_pressed(1, 2));
+
+    for _ in 0..2 {
+        assert_eq!(debouncer.update(&[0b100, 0]).count(), 0);
+    }
+    let events: Vec<_> = debouncer.update(&[0b100, 0]).collect();
+    assert_eq!(events, [key(1, 0, Action::Release)]);
+    assert!(!debouncer.is_pressed(1, 0));
+}
+
+#[test]
+fn scan_drives_one_row_low_at_a_time() {
+    let rows = [
+        Mock::new(&[
+            Transaction::set(State::High),
+            Transaction::set(State::Low),
+            Transaction::set(State::High),
+        ]),
+        Mock::new(&[
+            Transaction::set(State::High),
+            Transaction::set(State::Low),
+            Transaction::set(State::High),
+        ]),
+    ];
+    let columns = [
+        Mock::new(&[Transaction::get(State::High), Transaction::get(State::Low)]),
+        Mock::new(&[Transaction::get(State::Low), Transaction::get(State::High)]),
+    ];
+    let mut keypad = Keypad::new(rows, columns, NoopDelay::new());
+    assert_eq!(keypad.scan_raw(), [0b10, 0b01]);
+    let (rows, columns, _) = keypad.release();
+    for mut pin in rows.into_iter().chain(columns) {
+        pin.done();
+    }
+}
+
+#[test]
+fn ghosted_scans_are_ignored() {
+    // all four keys held: more than enough scans to debounce, were they valid
+    let scans = KEYPAD_DEBOUNCE_SCANS as usize;
+    let row = || {
+        let mut writes = vec![Transaction::set(State::High)];
+        for _ in 0..scans {
+            writes.extend([Transaction::set(State::Low), Transaction::set(State::High)]);
+        }
+        Mock::new(&writes)
+    };
+    let pressed = || Mock::new(&vec![Transaction::get(State::Low); 2 * scans]);
+    let mut keypad = Keypad::new([row(), row()], [pressed(), pressed()], NoopDelay::new());
+    for _ in 0..scans {
+        assert_eq!(keypad.scan().count(), 0);
+    }
+    assert!(!keypad.is_pressed(0, 0));
+    let (rows, columns, _) = keypad.release();
+    for mut pin in rows.into_iter().chain(columns) {
+        pin.done();
+    }
+}
@@ -0,0 +1,165 @@
+use core::cell::RefCell;
+
+use embedded_hal_mock::eh1::digital::{Mock, State, Transaction};
+use pico_app::led::{EdgeMode, Polarity, SelectionMode};
+use pico_app::ws2812::{ColorStrip, Rgb, pixel_pins};
+use pico_app::{ButtonDirection, LedRow};
+
+// Checks that every pin saw exactly the writes it expected.
+fn done<const N: usize>(row: LedRow<Mock, N>) {
+    for mut pin in row.release() {
+        pin.done();
+    }
+}
+
+// Bit n is set when pixel n is lit, i.e. when its pin is high.
+fn high_pins<const N: usize>(strip: &RefCell<ColorStrip<N>>) -> u32 {
+    let strip = strip.borrow();
+    (0..N)
+        .filter(|&index| strip.get(index) != Rgb::BLACK)
+        .fold(0, |mask, index| mask | 1 << index)
+}
+
+#[test]
+fn shift_switches_the_old_led_off_before_the_new_one_on() {
+    let row = [
+        Mock::new(&[
+            Transaction::set(State::Low),
+            Transaction::set(State::High),
+            Transaction::set(State::Low),
+            Transaction::set(State::High),
+            Transaction::set(State::Low),
+        ]),
+        Mock::new(&[
+            Transaction::set(State::Low),
+            Transaction::set(State::High),
+            Transaction::set(State::Low),
+        ]),
+        Mock::new(&[
+            Transaction::set(State::Low),
+            Transaction::set(State::High),
+            Transaction::toggle(),
+        ]),
+    ];
+    let mut row = LedRow::new(row);
+    row.set_on();
+    row.shift(ButtonDirection::Right);
+    row.shift(ButtonDirection::Left);
+    // wraps around to the last LED
+    row.shift(ButtonDirection::Left);
+    assert_eq!(row.active_led(), 2);
+    row.toggle();
+    done(row);
+}
+
+#[test]
+fn toggle_only_touches_the_selection() {
+    let row = [
+        Mock::new(&[
+            Transaction::set(State::Low),
+            Transaction::set(State::High),
+            Transaction::toggle(),
+            Transaction::toggle(),
+        ]),
+        Mock::new(&[Transaction::set(State::Low)]),
+        Mock::new(&[
+            Transaction::set(State::Low),
+            Transaction::set(State::High),
+            Transaction::toggle(),
+            Transaction::toggle(),
+        ]),
+    ];
+    let mut row = LedRow::new(row);
+    row.set_mask(0b101);
+    row.toggle();
+    row.toggle();
+    done(row);
+}
+
+#[test]
+fn active_low_drives_the_selection_low() {
+    let row = [
+        Mock::new(&[
+            Transaction::set(State::High),
+            Transaction::set(State::Low),
+            Transaction::set(State::High),
+        ]),
+        Mock::new(&[
+            Transaction::set(State::High),
+            Transaction::set(State::Low),
+            Transaction::toggle(),
+            Transaction::get_state(State::High),
+            Transaction::set(State::High),
+        ]),
+        Mock::new(&[Transaction::set(State::High), Transaction::set(State::Low)]),
+    ];
+    let mut row = LedRow::with_polarity(row, Polarity::ActiveLow);
+    row.set_on();
+    row.shift(ButtonDirection::Right);
+    row.toggle();
+    // toggled off, so the pin is high
+    assert!(!row.is_on());
+    row.set_active(2);
+    done(row);
+}
+
+#[test]
+fn one_led_is_lit_after_shifts_with_either_polarity() {
+    use ButtonDirection::{Left, Right};
+    // pins high: after construction, after the shifts, after selecting LED 1
+    for (polarity, high) in [
+        (Polarity::ActiveHigh, [0b0000, 0b1000, 0b0010]),
+        (Polarity::ActiveLow, [0b1111, 0b0111, 0b1101]),
+    ] {
+        let strip = RefCell::new(ColorStrip::<4>::new(Rgb::RED));
+        let mut row = LedRow::with_polarity(pixel_pins(&strip), polarity);
+        assert_eq!(high_pins(&strip), high[0]);
+        assert!(!row.is_on());
+
+        row.set_on();
+        for direction in [Right, Right, Left, Left, Left] {
+            row.shift(direction);
+        }
+        assert_eq!(row.active_led(), 3);
+        assert!(row.is_on());
+        assert_eq!(high_pins(&strip), high[1]);
+
+        row.set_active(1);
+        assert!(row.is_on());
+        assert_eq!(high_pins(&strip), high[2]);
+    }
+}
+
+#[test]
+fn selection_moves_clamps_and_meters() {
+    let strip = RefCell::new(ColorStrip::<5>::new(Rgb::RED));
+    let mut row = LedRow::new(pixel_pins(&strip));
+    row.set_range(1..3);
+    assert_eq!(high_pins(&strip), 0b00110);
+    for _ in 0..3 {
+        row.shift(ButtonDirection::Right);
+    }
+    assert_eq!(row.mask(), 0b10001);
+    assert_eq!(high_pins(&strip), 0b10001);
+
+    row.set_edge_mode(EdgeMode::Clamp);
+    row.set_mask(0b11000);
+    row.shift(ButtonDirection::Right);
+    assert_eq!(row.mask(), 0b11000);
+    row.shift(ButtonDirection::Left);
+    assert_eq!(high_pins(&strip), 0b01100);
+
+    row.set_mode(SelectionMode::Meter);
+    row.set_level(0);
+    assert_eq!(high_pins(&strip), 0);
+    for _ in 0..7 {
+        row.shift(ButtonDirection::Right);
+    }
+    assert_eq!(row.level(), 5);
+    row.shift(ButtonDirection::Left);
+    assert_eq!(high_pins(&strip), 0b01111);
+    row.show_level(50, 100);
+    assert_eq!(row.level(), 3);
+    row.set_mask(u32::MAX);
+    assert_eq!(row.mask(), 0b11111);
+}
@@ -0,0 +1,131 @@
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::rc::Rc;
+
+use embedded_hal::digital::{ErrorType, OutputPin, PinState};
+use pico_app::FrameBuffer;
+use pico_app::matrix::{
+    Charlieplex, LedMatrix, ScanDisplay, TriStatePin, charlieplex_index, charlieplex_pins,
+    line_period_us,
+};
+
+// A pin the test can look at while the display holds it, `None` while it
+// is disconnected.
+#[derive(Clone, Default)]
+struct FakePin(Rc<Cell<Option<bool>>>);
+
+impl FakePin {
+    fn level(&self) -> Option<bool> {
+        self.0.get()
+    }
+}
+
+impl ErrorType for FakePin {
+    type Error = Infallible;
+}
+
+impl OutputPin for FakePin {
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        self.0.set(Some(false));
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        self.0.set(Some(true));
+        Ok(())
+    }
+}
+
+impl TriStatePin for FakePin {
+    fn set_high_impedance(&mut self) {
+        self.0.set(None);
+    }
+
+    fn drive(&mut self, state: PinState) {
+        self.0.set(Some(state == PinState::High));
+    }
+}
+
+fn levels<const N: usize>(pins: &[FakePin; N]) -> [Option<bool>; N] {
+    pins.each_ref().map(FakePin::level)
+}
+
+const LOW: Option<bool> = Some(false);
+const HIGH: Option<bool> = Some(true);
+
+#[test]
+fn charlieplex_index_and_pins_round_trip() {
+    for pins in 2..=10 {
+        let leds = pins * (pins - 1);
+        let mut pairs = HashSet::new();
+        for index in 0..leds {
+            let (anode, cathode) = charlieplex_pins(index, pins);
+            assert!(anode < pins && cathode < pins);
+            assert_ne!(anode, cathode);
+            assert_eq!(charlieplex_index(anode, cathode, pins), index);
+            pairs.insert((anode, cathode));
+        }
+        // every ordered pair of pins has its LED
+        assert_eq!(pairs.len(), leds);
+    }
+}
+
+#[test]
+fn line_period_splits_the_refresh_between_the_lines() {
+    assert_eq!(line_period_us(100, 5), 2000);
+    assert_eq!(line_period_us(100, 10), 1000);
+    assert_eq!(line_period_us(0, 5), 1_000_000);
+}
+
+#[test]
+fn matrix_drives_one_row_and_pulls_the_lit_columns_low() {
+    let rows: [FakePin; 2] = Default::default();
+    let columns: [FakePin; 3] = Default::default();
+    let mut matrix = LedMatrix::new(rows.clone(), columns.clone());
+    assert_eq!(ScanDisplay::<3, 2>::lines(&matrix), 2);
+    assert_eq!(levels(&rows), [LOW; 2]);
+    assert_eq!(levels(&columns), [HIGH; 3]);
+
+    let mut frame = FrameBuffer::<3, 2>::new();
+    frame.set(0, 0, 255);
+    frame.set(2, 1, 255);
+    // too dim to light up
+    frame.set(1, 1, 100);
+
+    matrix.show_line(&frame, 0);
+    assert_eq!(levels(&rows), [HIGH, LOW]);
+    assert_eq!(levels(&columns), [LOW, HIGH, HIGH]);
+    matrix.show_line(&frame, 1);
+    assert_eq!(levels(&rows), [LOW, HIGH]);
+    assert_eq!(levels(&columns), [HIGH, HIGH, LOW]);
+
+    ScanDisplay::<3, 2>::blank(&mut matrix);
+    assert_eq!(levels(&rows), [LOW; 2]);
+}
+
+#[test]
+fn charlieplex_drives_the_anode_and_the_lit_cathodes_only() {
+    let pins: [FakePin; 3] = Default::default();
+    let mut charlieplex = Charlieplex::new(pins.clone());
+    assert_eq!(ScanDisplay::<3, 2>::lines(&charlieplex), 3);
+    assert_eq!(levels(&pins), [None; 3]);
+
+    // pixel 1 sits between anode 0 and cathode 2, pixel 3 between anode 1
+    // and cathode 2
+    let mut frame = FrameBuffer::<3, 2>::new();
+    frame.set(1, 0, 255);
+    frame.set(0, 1, 255);
+
+    charlieplex.show_line(&frame, 0);
+    assert_eq!(levels(&pins), [HIGH, None, LOW]);
+    charlieplex.show_line(&frame, 1);
+    assert_eq!(levels(&pins), [None, HIGH, LOW]);
+    // nothing lit on anode 2, so it stays disconnected as well
+    charlieplex.show_line(&frame, 2);
+    assert_eq!(levels(&pins), [None; 3]);
+
+    charlieplex.show_line(&frame, 0);
+    charlieplex.blank();
+    assert_eq!(levels(&pins), [None; 3]);
+}
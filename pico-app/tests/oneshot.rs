@@ -0,0 +1,103 @@
+mod common;
+
+use std::task::Poll;
+
+use futures::executor::block_on;
+use futures::{FutureExt, join};
+use pico_app::led::{LedControl, QueryError};
+use pico_app::oneshot::{self, Canceled, Oneshot};
+
+use common::{WakeCounter, poll};
+
+#[test]
+fn receives_what_was_sent() {
+    let slot = Oneshot::new();
+    let (sender, receiver) = oneshot::channel(&slot).unwrap();
+    sender.send(7).unwrap();
+    assert_eq!(block_on(receiver), Ok(7));
+}
+
+#[test]
+fn send_wakes_the_waiting_receiver() {
+    let slot = Oneshot::new();
+    let (sender, mut receiver) = oneshot::channel(&slot).unwrap();
+    let counter = WakeCounter::new();
+    assert_eq!(poll(&mut receiver, &counter.waker()), Poll::Pending);
+    sender.send("pong").unwrap();
+    assert!(counter.count() > 0);
+    assert_eq!(
+        poll(&mut receiver, &counter.waker()),
+        Poll::Ready(Ok("pong"))
+    );
+}
+
+#[test]
+fn dropped_sender_cancels_the_receiver() {
+    let slot = Oneshot::<u8>::new();
+    let (sender, mut receiver) = oneshot::channel(&slot).unwrap();
+    let counter = WakeCounter::new();
+    assert_eq!(poll(&mut receiver, &counter.waker()), Poll::Pending);
+    drop(sender);
+    assert_eq!(counter.count(), 1);
+    assert_eq!(receiver.try_receive(), Err(Canceled));
+}
+
+#[test]
+fn dropped_receiver_cancels_the_sender() {
+    let slot = Oneshot::new();
+    let (mut sender, receiver) = oneshot::channel(&slot).unwrap();
+    let counter = WakeCounter::new();
+    {
+        let mut canceled = Box::pin(sender.canceled());
+        assert_eq!(poll(&mut canceled, &counter.waker()), Poll::Pending);
+        drop(receiver);
+        assert_eq!(counter.count(), 1);
+        assert_eq!(poll(&mut canceled, &counter.waker()), Poll::Ready(()));
+    }
+    assert!(sender.is_canceled());
+    assert_eq!(sender.send(3), Err(3));
+}
+
+#[test]
+fn slot_is_reused_once_both_ends_are_gone() {
+    let slot = Oneshot::new();
+    let (sender, receiver) = oneshot::channel(&slot).unwrap();
+    assert!(oneshot::channel(&slot).is_none());
+    sender.send(1).unwrap();
+    assert!(oneshot::channel(&slot).is_none());
+    drop(receiver);
+
+    // the unreceived item is gone with the old pair
+    let (sender, mut receiver) = oneshot::channel(&slot).unwrap();
+    assert_eq!(receiver.try_receive(), Ok(None));
+    drop(sender);
+    assert_eq!(receiver.try_receive(), Err(Canceled));
+}
+
+#[test]
+fn led_task_answers_the_active_led() {
+    let slot = Oneshot::new();
+    let control = LedControl::new(10, 500, &slot);
+    assert_eq!(block_on(control.active_led()), Err(QueryError::NoLedTask));
+
+    control.attach();
+    let led_task = async {
+        control.changed().await;
+        control.take_query().unwrap().send(4).unwrap();
+    };
+    let (active, ()) = block_on(async { join!(control.active_led(), led_task) });
+    assert_eq!(active, Ok(4));
+
+    // a second request while the first one waits is turned away
+    let mut request = control.active_led().boxed_local();
+    let counter = WakeCounter::new();
+    assert_eq!(poll(&mut request, &counter.waker()), Poll::Pending);
+    assert_eq!(block_on(control.active_led()), Err(QueryError::Busy));
+
+    // a request the LED task drops unanswered ends without an LED
+    drop(control.take_query());
+    assert_eq!(
+        poll(&mut request, &counter.waker()),
+        Poll::Ready(Err(QueryError::NoAnswer))
+    );
+}
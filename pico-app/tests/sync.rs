@@ -0,0 +1,132 @@
+mod common;
+
+use std::task::Poll;
+
+use futures::executor::block_on;
+use pico_app::sync::{MAX_WAITERS, Mutex, RwLock, Semaphore};
+
+use common::{WakeCounter, poll};
+
+#[test]
+fn waiters_are_woken_in_arrival_order() {
+    let semaphore = Semaphore::new(1);
+    let held = semaphore.try_acquire().unwrap();
+    let counters = [WakeCounter::new(), WakeCounter::new(), WakeCounter::new()];
+    let mut waiters = [
+        semaphore.acquire(),
+        semaphore.acquire(),
+        semaphore.acquire(),
+    ];
+    for (waiter, counter) in waiters.iter_mut().zip(&counters) {
+        assert!(poll(waiter, &counter.waker()).is_pending());
+    }
+
+    drop(held);
+    for (i, counter) in counters.iter().enumerate() {
+        assert_eq!(counter.count(), 1);
+        // the ones behind can't jump the queue, even when polled first
+        for (waiter, later) in waiters[i + 1..].iter_mut().zip(&counters[i + 1..]) {
+            assert_eq!(later.count(), 0);
+            assert!(poll(waiter, &later.waker()).is_pending());
+        }
+        let Poll::Ready(permit) = poll(&mut waiters[i], &counter.waker()) else {
+            panic!("waiter {i} should have the permit");
+        };
+        drop(permit);
+    }
+    assert_eq!(semaphore.available_permits(), 1);
+}
+
+#[test]
+fn queued_waiters_keep_large_requests_from_starving() {
+    let semaphore = Semaphore::new(2);
+    let held = semaphore.try_acquire().unwrap();
+    let counter = WakeCounter::new();
+    let mut both = semaphore.acquire_many(2);
+    assert!(poll(&mut both, &counter.waker()).is_pending());
+    // one permit is free, but it's promised to the waiter at the front
+    assert!(semaphore.try_acquire().is_none());
+    drop(held);
+    assert!(poll(&mut both, &counter.waker()).is_ready());
+}
+
+#[test]
+fn canceled_waiter_passes_its_turn_on() {
+    let semaphore = Semaphore::new(1);
+    let held = semaphore.try_acquire().unwrap();
+    let (first, second) = (WakeCounter::new(), WakeCounter::new());
+    let mut canceled = semaphore.acquire();
+    let mut next = semaphore.acquire();
+    assert!(poll(&mut canceled, &first.waker()).is_pending());
+    assert!(poll(&mut next, &second.waker()).is_pending());
+
+    drop(held);
+    assert_eq!((first.count(), second.count()), (1, 0));
+    drop(canceled);
+    assert_eq!(second.count(), 1);
+    assert!(poll(&mut next, &second.waker()).is_ready());
+}
+
+#[test]
+fn full_queue_makes_waiters_retry_instead_of_panicking() {
+    let semaphore = Semaphore::new(1);
+    let held = semaphore.try_acquire().unwrap();
+    let counter = WakeCounter::new();
+    let mut queued: Vec<_> = (0..MAX_WAITERS).map(|_| semaphore.acquire()).collect();
+    for waiter in &mut queued {
+        assert!(poll(waiter, &counter.waker()).is_pending());
+    }
+
+    let late = WakeCounter::new();
+    let mut overflow = semaphore.acquire();
+    assert!(poll(&mut overflow, &late.waker()).is_pending());
+    // asks to be polled again right away
+    assert_eq!(late.count(), 1);
+
+    drop(queued);
+    assert!(poll(&mut overflow, &late.waker()).is_pending());
+    drop(held);
+    assert!(poll(&mut overflow, &late.waker()).is_ready());
+}
+
+#[test]
+fn mutex_hands_out_one_guard_at_a_time() {
+    let mutex = Mutex::new(0);
+    {
+        let mut guard = block_on(mutex.lock());
+        *guard += 1;
+        assert!(mutex.try_lock().is_none());
+    }
+    *mutex.try_lock().unwrap() += 1;
+    assert_eq!(mutex.into_inner(), 2);
+}
+
+#[test]
+fn writer_excludes_readers() {
+    let lock = RwLock::new(1);
+    let first = block_on(lock.read());
+    let second = block_on(lock.read());
+    assert_eq!(*first + *second, 2);
+
+    let counter = WakeCounter::new();
+    let mut write = Box::pin(lock.write());
+    assert!(poll(&mut write, &counter.waker()).is_pending());
+    // readers queue up behind the writer
+    let mut late_read = Box::pin(lock.read());
+    assert!(poll(&mut late_read, &counter.waker()).is_pending());
+
+    drop(first);
+    assert!(poll(&mut write, &counter.waker()).is_pending());
+    drop(second);
+    let Poll::Ready(mut guard) = poll(&mut write, &counter.waker()) else {
+        panic!("the readers are gone");
+    };
+    *guard = 5;
+    assert!(poll(&mut late_read, &counter.waker()).is_pending());
+
+    drop(guard);
+    let Poll::Ready(guard) = poll(&mut late_read, &counter.waker()) else {
+        panic!("the writer is gone");
+    };
+    assert_eq!(*guard, 5);
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use std::cell::RefCell;
+use std::pin::pin;
+
+use futures::executor::block_on;
+use futures::future::join;
+use pico_app::LedRow;
+use pico_app::channel::Channel;
+use pico_app::input::{Action, Button, Directions, InputEvent};
+use pico_app::led::LedControl;
+use pico_app::oneshot::Oneshot;
+use pico_app::sync::Mutex;
+use pico_app::tasks;
+use pico_app::ws2812::{ColorStrip, Rgb, pixel_pins};
+
+use common::{FakePin, FakeTime, run_until_stalled};
+
+fn lit<const N: usize>(strip: &RefCell<ColorStrip<N>>) -> [bool; N] {
+    let strip = strip.borrow();
+    core::array::from_fn(|i| strip.get(i) != Rgb::BLACK)
+}
+
+fn event(button: Button, action: Action, timestamp_ms: u32) -> InputEvent {
+    InputEvent {
+        button,
+        action,
+        timestamp_ms,
+    }
+}
+
+#[test]
+fn input_task_reports_press_and_release_once_per_push() {
+    let channel: Channel<_> = Channel::new();
+    let mut receiver = channel.get_receiver();
+    let pin = FakePin::new(true);
+    let time = FakeTime::default();
+    let events = RefCell::new(Vec::new());
+    let task = tasks::input_task(
+        pin.clone(),
+        Button::Left,
+        channel.get_sender(),
+        time.clone(),
+        time.clone(),
+    );
+    let collect = async {
+        while let Some(event) = receiver.receive().await {
+            events.borrow_mut().push(event);
+        }
+    };
+    let mut both = pin!(join(task, collect));
+    assert!(run_until_stalled(both.as_mut()).is_pending());
+
+    time.advance(5);
+    pin.set_high(false);
+    assert!(run_until_stalled(both.as_mut()).is_pending());
+    assert_eq!(*events.borrow(), [event(Button::Left, Action::Press, 5)]);
+
+    // the contacts bounce during the debounce delay
+    pin.set_high(true);
+    assert!(run_until_stalled(both.as_mut()).is_pending());
+    pin.set_high(false);
+    time.advance(tasks::DEBOUNCE_MS);
+    assert!(run_until_stalled(both.as_mut()).is_pending());
+    assert_eq!(events.borrow().len(), 1);
+
+    time.advance(50);
+    pin.set_high(true);
+    assert!(run_until_stalled(both.as_mut()).is_pending());
+    assert_eq!(
+        events.borrow()[1..],
+        [event(Button::Left, Action::Release, 255)]
+    );
+}
+
+#[test]
+fn input_task_stops_once_the_receiver_is_gone() {
+    let channel: Channel<_> = Channel::new();
+    let pin = FakePin::new(true);
+    let time = FakeTime::default();
+    let mut task = pin!(tasks::input_task(
+        pin.clone(),
+        Button::Right,
+        channel.get_sender(),
+        time.clone(),
+        time,
+    ));
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    drop(channel.get_receiver());
+    pin.set_high(false);
+    assert!(run_until_stalled(task.as_mut()).is_ready());
+}
+
+#[test]
+fn led_task_moves_on_presses_and_follows_the_control() {
+    let strip = RefCell::new(ColorStrip::<4>::new(Rgb::RED));
+    let row = Mutex::new(LedRow::new(pixel_pins(&strip)));
+    let slot = Oneshot::new();
+    let control = LedControl::new(4, tasks::BLINK_PERIOD_MS, &slot);
+    let channel: Channel<_> = Channel::new();
+    let sender = channel.get_sender();
+    let mut task = pin!(tasks::led_task(
+        &row,
+        Directions(channel.get_receiver()),
+        &control
+    ));
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    assert!(control.is_attached());
+    assert_eq!(lit(&strip), [true, false, false, false]);
+
+    block_on(sender.send(event(Button::Right, Action::Press, 0))).unwrap();
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    block_on(sender.send(event(Button::Right, Action::Release, 0))).unwrap();
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    assert_eq!(lit(&strip), [false, true, false, false]);
+
+    control.select(3);
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    assert_eq!(lit(&strip), [false, false, false, true]);
+    // past the end of the row
+    control.select(4);
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    assert_eq!(lit(&strip), [false, false, false, true]);
+
+    let mut query = pin!(control.active_led());
+    assert!(run_until_stalled(query.as_mut()).is_pending());
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    assert_eq!(run_until_stalled(query.as_mut()), Ok(3).into());
+
+    // without buttons the control still works
+    drop(sender);
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    control.select(0);
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    assert_eq!(lit(&strip), [true, false, false, false]);
+}
+
+#[test]
+fn blink_task_toggles_at_the_control_period() {
+    let strip = RefCell::new(ColorStrip::<2>::new(Rgb::RED));
+    let row = Mutex::new(LedRow::new(pixel_pins(&strip)));
+    row.try_lock().unwrap().set_on();
+    let slot = Oneshot::new();
+    let control = LedControl::new(2, 100, &slot);
+    let time = FakeTime::default();
+    let mut task = pin!(tasks::blink_task(&row, time.clone(), &control));
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+
+    time.advance(99);
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    assert_eq!(lit(&strip), [true, false]);
+    time.advance(1);
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    assert_eq!(lit(&strip), [false, false]);
+
+    // a new period starts over at once
+    time.advance(50);
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    control.set_blink_ms(300);
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    time.advance(299);
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    assert_eq!(lit(&strip), [false, false]);
+    time.advance(1);
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    assert_eq!(lit(&strip), [true, false]);
+    // a shorter one doesn't wait for the long one to run out either
+    time.advance(100);
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    control.set_blink_ms(20);
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    time.advance(20);
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    assert_eq!(lit(&strip), [false, false]);
+    control.set_blink_ms(300);
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+
+    // another task holding the row holds up the blinking
+    let guard = row.try_lock().unwrap();
+    time.advance(300);
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    assert_eq!(lit(&strip), [false, false]);
+    drop(guard);
+    assert!(run_until_stalled(task.as_mut()).is_pending());
+    assert_eq!(lit(&strip), [true, false]);
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use std::cell::RefCell;
+use std::pin::pin;
+use std::task::Poll;
+
+use pico_app::FrameBuffer;
+use pico_app::font::{glyph, glyph_pixel, glyph_width};
+use pico_app::text::{TextDisplay, render_scroll, scroll_steps, text_width};
+
+use common::{FakeTime, run_until_stalled};
+
+// The frame row by row, `#` where a pixel is lit.
+fn rows<const W: usize, const H: usize>(frame: &FrameBuffer<W, H>) -> Vec<String> {
+    (0..H)
+        .map(|y| {
+            (0..W)
+                .map(|x| if frame.get(x, y) > 0 { '#' } else { '.' })
+                .collect()
+        })
+        .collect()
+}
+
+fn is_blank<const W: usize, const H: usize>(frame: &FrameBuffer<W, H>) -> bool {
+    rows(frame).iter().all(|row| !row.contains('#'))
+}
+
+#[test]
+fn glyphs_are_looked_up_case_insensitive() {
+    assert_eq!(glyph('a'), glyph('A'));
+    assert_eq!(glyph('z'), glyph('Z'));
+    assert_ne!(glyph('A'), glyph('B'));
+    // anything the font doesn't know is a box
+    assert_eq!(glyph('~'), glyph('é'));
+    assert_eq!(glyph('~'), [0b11111, 0b10001, 0b10001, 0b10001, 0b11111]);
+
+    assert!(glyph_pixel('T', 0, 0));
+    assert!(!glyph_pixel('T', 0, 1));
+    assert!(glyph_pixel('T', 2, 4));
+    assert!(!glyph_pixel('T', 5, 0));
+    assert!(!glyph_pixel('T', 0, 5));
+}
+
+#[test]
+fn glyphs_are_as_wide_as_their_lit_columns() {
+    assert_eq!(glyph_width('M'), 5);
+    assert_eq!(glyph_width('H'), 4);
+    assert_eq!(glyph_width('I'), 3);
+    assert_eq!(glyph_width('!'), 1);
+    // blank glyphs still take some room
+    assert_eq!(glyph_width(' '), 3);
+
+    // each glyph plus the gap after it
+    assert_eq!(text_width(""), 0);
+    assert_eq!(text_width("HI"), 5 + 4);
+    assert_eq!(text_width("I !"), 4 + 4 + 2);
+}
+
+#[test]
+fn scroll_steps_run_until_the_text_has_left() {
+    assert_eq!(scroll_steps("", 5), 5);
+    assert_eq!(scroll_steps("HI", 5), 14);
+    assert_eq!(scroll_steps("HI", 8), 17);
+
+    let mut frame = FrameBuffer::<5, 5>::new();
+    // `!` reaches the left edge two steps before the end
+    render_scroll("I!", scroll_steps("I!", 5) - 2, &mut frame);
+    assert_eq!(rows(&frame)[0], "#....");
+    for step in scroll_steps("I!", 5) - 1..=scroll_steps("I!", 5) {
+        render_scroll("I!", step, &mut frame);
+        assert!(is_blank(&frame));
+    }
+}
+
+#[test]
+fn text_enters_on_the_right_one_column_per_step() {
+    let mut frame = FrameBuffer::<5, 5>::new();
+    render_scroll("I!", 0, &mut frame);
+    assert!(is_blank(&frame));
+
+    render_scroll("I!", 1, &mut frame);
+    assert_eq!(rows(&frame), ["....#", ".....", ".....", ".....", "....#"]);
+
+    render_scroll("I!", 5, &mut frame);
+    assert_eq!(rows(&frame), ["###.#", ".#..#", ".#..#", ".#...", "###.#"]);
+
+    render_scroll("I!", 7, &mut frame);
+    assert_eq!(rows(&frame), ["#.#..", "..#..", "..#..", ".....", "#.#.."]);
+}
+
+#[test]
+fn text_is_centred_on_taller_displays() {
+    let mut frame = FrameBuffer::<3, 7>::new();
+    render_scroll("I", 3, &mut frame);
+    assert_eq!(
+        rows(&frame),
+        ["...", "###", ".#.", ".#.", ".#.", "###", "..."]
+    );
+}
+
+#[test]
+fn scroll_takes_a_step_per_delay_until_the_text_has_left() {
+    let time = FakeTime::default();
+    let frame = RefCell::new(FrameBuffer::<5, 5>::new());
+    let mut display = TextDisplay::new(&frame, time.clone());
+    display.set_scroll_speed(100);
+
+    let mut scroll = pin!(display.scroll("I"));
+    assert_eq!(run_until_stalled(scroll.as_mut()), Poll::Pending);
+    assert!(is_blank(&frame.borrow()));
+    time.advance(99);
+    assert_eq!(run_until_stalled(scroll.as_mut()), Poll::Pending);
+    assert!(is_blank(&frame.borrow()));
+    time.advance(1);
+    assert_eq!(run_until_stalled(scroll.as_mut()), Poll::Pending);
+    assert_eq!(rows(&frame.borrow())[0], "....#");
+
+    // one delay after each of the steps 0 to `scroll_steps`
+    for _ in 1..scroll_steps("I", 5) {
+        time.advance(100);
+        assert_eq!(run_until_stalled(scroll.as_mut()), Poll::Pending);
+    }
+    assert!(is_blank(&frame.borrow()));
+    time.advance(100);
+    assert_eq!(run_until_stalled(scroll.as_mut()), Poll::Ready(()));
+    assert_eq!(time.now(), 100 * (scroll_steps("I", 5) as u32 + 1));
+}
+
+#[test]
+fn show_centres_a_single_character() {
+    let frame = RefCell::new(FrameBuffer::<5, 5>::new());
+    let mut display = TextDisplay::new(&frame, FakeTime::default());
+    display.show('I');
+    assert_eq!(
+        rows(&frame.borrow()),
+        [".###.", "..#..", "..#..", "..#..", ".###."]
+    );
+    display.clear();
+    assert!(is_blank(&frame.borrow()));
+}
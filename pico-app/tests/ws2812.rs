@@ -0,0 +1,101 @@
+use core::cell::RefCell;
+
+use pico_app::framebuffer::Display;
+use pico_app::ws2812::{
+    BIT_RATE_HZ, CYCLES_PER_BIT, ColorStrip, Rgb, T1, T2, T3, bit_timing, clock_divider, encode,
+    frame_time_us, pixel_pins,
+};
+use pico_app::{ButtonDirection, LedRow};
+
+// The bits in the order the state machine shifts them out.
+fn shifted_bits(word: u32) -> Vec<bool> {
+    (0..24).map(|bit| word & (1 << (31 - bit)) != 0).collect()
+}
+
+fn byte_bits(byte: u8) -> Vec<bool> {
+    (0..8).map(|bit| byte & (0x80 >> bit) != 0).collect()
+}
+
+fn cycles_ns(cycles: u32) -> u64 {
+    cycles as u64 * 1_000_000_000 / (BIT_RATE_HZ * CYCLES_PER_BIT) as u64
+}
+
+#[test]
+fn pixels_go_out_green_red_blue_msb_first() {
+    let color = Rgb::new(0x81, 0x42, 0x3c);
+    let word = encode(color);
+    assert_eq!(word, 0x4281_3c00);
+    let expected: Vec<bool> = [color.g, color.r, color.b]
+        .into_iter()
+        .flat_map(byte_bits)
+        .collect();
+    assert_eq!(shifted_bits(word), expected);
+    // the bits below the 24 pulled per pixel stay clear
+    assert_eq!(encode(Rgb::WHITE) & 0xff, 0);
+}
+
+#[test]
+fn strip_encodes_every_pixel_gamma_corrected() {
+    let mut strip = ColorStrip::<3>::new(Rgb::WHITE);
+    strip.set(0, Rgb::GREEN);
+    strip.set(2, Rgb::WHITE);
+    assert_eq!(strip.encode(), [0xff00_0000, 0, 0xffff_ff00]);
+    strip.set(1, Rgb::new(128, 0, 0));
+    let dimmed = strip.encode()[1] >> 16;
+    assert!(dimmed > 0 && dimmed < 128);
+}
+
+#[test]
+fn bit_phases_are_within_ws2812b_timing() {
+    // state machine cycles are 125 ns long
+    assert_eq!(CYCLES_PER_BIT, 10);
+    assert_eq!((T1, T2, T3), (2, 5, 3));
+    assert_eq!(cycles_ns(CYCLES_PER_BIT), 1250);
+
+    // datasheet: T0H 0.4 µs, T0L 0.85 µs, T1H 0.8 µs, T1L 0.45 µs, ±150 ns
+    let (high, low) = bit_timing(false);
+    assert_eq!(high + low, CYCLES_PER_BIT);
+    assert!((250..=550).contains(&cycles_ns(high)));
+    assert!((700..=1000).contains(&cycles_ns(low)));
+    let (high, low) = bit_timing(true);
+    assert_eq!(high + low, CYCLES_PER_BIT);
+    assert!((650..=950).contains(&cycles_ns(high)));
+    assert!((300..=600).contains(&cycles_ns(low)));
+}
+
+#[test]
+fn divider_hits_the_cycle_rate() {
+    // 125 MHz / 8 MHz = 15.625 = 15 + 160/256
+    assert_eq!(clock_divider(125_000_000), (15, 160));
+    assert_eq!(clock_divider(133_000_000), (16, 160));
+    assert_eq!(clock_divider(48_000_000), (6, 0));
+    // rounded to the nearest 1/256: 100 MHz / 8 MHz = 12.5
+    assert_eq!(clock_divider(100_000_000), (12, 128));
+    assert_eq!(frame_time_us(10), 300);
+}
+
+#[test]
+fn colors() {
+    assert_eq!(Rgb::hsv(0, 255, 255), Rgb::RED);
+    assert_eq!(Rgb::hsv(85, 255, 255).g, 255);
+    assert_eq!(Rgb::hsv(170, 255, 255).b, 255);
+    assert_eq!(Rgb::hsv(42, 0, 200), Rgb::new(200, 200, 200));
+    assert_eq!(Rgb::WHITE.gamma(), Rgb::WHITE);
+    assert_eq!(Rgb::WHITE.scale(0), Rgb::BLACK);
+    assert_eq!(Rgb::YELLOW.scale(128), Rgb::new(128, 128, 0));
+}
+
+#[test]
+fn pixels_work_as_led_pins_and_display() {
+    let strip = RefCell::new(ColorStrip::<3>::new(Rgb::RED));
+    let mut row = LedRow::new(pixel_pins(&strip));
+    row.toggle();
+    assert_eq!(strip.borrow().get(0), Rgb::RED);
+    row.shift(ButtonDirection::Right);
+    assert_eq!(strip.borrow().get(0), Rgb::BLACK);
+    assert_eq!(strip.borrow().get(1), Rgb::RED);
+
+    let mut display = &strip;
+    display.write_pixel(2, 0, 255);
+    assert_eq!(strip.borrow().get(2), Rgb::RED);
+}
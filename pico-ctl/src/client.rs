@@ -0,0 +1,97 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+
+use pico_protocol::{ButtonEvent, Frame, FrameReader, Request, Response, encode_to_vec};
+
+// Talks to the `remote` firmware over anything that reads and writes bytes,
+// usually a serial port. Events that arrive while waiting for a response are
+// kept for `next_event`.
+pub struct Client<P> {
+    port: P,
+    reader: FrameReader,
+    // received, but not fed to `reader` yet
+    pending: Vec<u8>,
+    events: VecDeque<ButtonEvent>,
+}
+
+impl<P: Read + Write> Client<P> {
+    pub fn new(port: P) -> Self {
+        Self {
+            port,
+            reader: FrameReader::new(),
+            pending: Vec::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    fn receive(&mut self) -> io::Result<Response> {
+        loop {
+            if !self.pending.is_empty() {
+                let (frame, remaining) = self.reader.feed::<Response>(&self.pending);
+                let used = self.pending.len() - remaining.len();
+                self.pending.drain(..used);
+                match frame {
+                    Some(Frame::Message(response)) => return Ok(response),
+                    Some(Frame::Invalid) => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "malformed frame, is the firmware built with `remote`?",
+                        ));
+                    }
+                    None => continue,
+                }
+            }
+            let mut chunk = [0; 64];
+            let len = self.port.read(&mut chunk)?;
+            if len == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            self.pending.extend_from_slice(&chunk[..len]);
+        }
+    }
+
+    // Sends one request and waits for its response.
+    pub fn request(&mut self, request: Request) -> io::Result<Response> {
+        let frame = encode_to_vec(&request).map_err(io::Error::other)?;
+        self.port.write_all(&frame)?;
+        self.port.flush()?;
+        loop {
+            match self.receive()? {
+                Response::Event(event) => self.events.push_back(event),
+                response => return Ok(response),
+            }
+        }
+    }
+
+    // Waits for the next input event, subscribe first. Nothing happening
+    // for a while isn't an error here, read timeouts are sat out.
+    pub fn next_event(&mut self) -> io::Result<ButtonEvent> {
+        if let Some(event) = self.events.pop_front() {
+            return Ok(event);
+        }
+        loop {
+            match self.receive() {
+                Ok(Response::Event(event)) => return Ok(event),
+                Ok(response) => return Err(unexpected(response)),
+                Err(error) if error.kind() == io::ErrorKind::TimedOut => continue,
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+// Turns a response other than `Ok` into an error.
+pub fn expect_ok(response: Response) -> io::Result<()> {
+    match response {
+        Response::Ok => Ok(()),
+        Response::Error(error) => Err(io::Error::other(format!("the board says {error:?}"))),
+        response => Err(unexpected(response)),
+    }
+}
+
+pub fn unexpected(response: Response) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("unexpected response {response:?}"),
+    )
+}
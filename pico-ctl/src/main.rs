@@ -0,0 +1,172 @@
+// Remote control for boards running a firmware built with `remote`, over the
+// USB serial port. `pico-ctl simulate` stands in for the board.
+mod client;
+#[cfg(unix)]
+mod sim;
+#[cfg(all(test, unix))]
+mod tests;
+
+use std::io;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use pico_protocol::{Request, Response};
+
+use crate::client::{Client, expect_ok, unexpected};
+
+const USAGE: &str = "usage: pico-ctl PORT COMMAND
+       pico-ctl simulate [--verbose]
+commands:
+  ping       check that the board answers
+  tasks      executor tasks and how often they were polled
+  led N      select LED N
+  blink MS   set the blink period in milliseconds
+  events     print button presses and releases until interrupted
+  stats      uptime, executor and I/O counters
+simulate opens a pseudo-terminal that answers like a board and prints its
+path, to be used as PORT; with --verbose it logs every request it answers";
+
+const TIMEOUT: Duration = Duration::from_secs(1);
+// how long the simulator waits for requests before it presses a button
+const SIM_POLL: Duration = Duration::from_millis(100);
+
+enum Command {
+    Ping,
+    Tasks,
+    Led(u8),
+    Blink(u32),
+    Events,
+    Stats,
+}
+
+fn parse(args: &[String]) -> Option<Command> {
+    let command = match args {
+        [command] if command == "ping" => Command::Ping,
+        [command] if command == "tasks" => Command::Tasks,
+        [command, led] if command == "led" => Command::Led(led.parse().ok()?),
+        [command, period] if command == "blink" => Command::Blink(period.parse().ok()?),
+        [command] if command == "events" => Command::Events,
+        [command] if command == "stats" => Command::Stats,
+        _ => return None,
+    };
+    Some(command)
+}
+
+fn run<P: io::Read + io::Write>(client: &mut Client<P>, command: Command) -> io::Result<()> {
+    match command {
+        Command::Ping => match client.request(Request::Ping)? {
+            Response::Pong => println!("pong"),
+            response => return Err(unexpected(response)),
+        },
+        Command::Tasks => {
+            println!("id  polls       state");
+            for id in 0..=u8::MAX {
+                match client.request(Request::GetTask(id))? {
+                    Response::Task(Some(task)) => {
+                        let state = if task.finished { "finished" } else { "running" };
+                        println!("{:<3} {:<11} {}", task.id, task.polls, state);
+                    }
+                    Response::Task(None) if id == 0 => {
+                        println!("(this firmware keeps no task statistics)")
+                    }
+                    Response::Task(None) => break,
+                    response => return Err(unexpected(response)),
+                }
+            }
+        }
+        Command::Led(led) => expect_ok(client.request(Request::SetActiveLed(led))?)?,
+        Command::Blink(period_ms) => {
+            expect_ok(client.request(Request::SetBlinkPeriod { period_ms })?)?
+        }
+        Command::Events => {
+            expect_ok(client.request(Request::SubscribeEvents(true))?)?;
+            loop {
+                let event = client.next_event()?;
+                let action = if event.pressed { "pressed" } else { "released" };
+                println!(
+                    "{:>10} ms  {:?} {}",
+                    event.timestamp_ms, event.button, action
+                );
+            }
+        }
+        Command::Stats => match client.request(Request::GetStats)? {
+            Response::Stats(stats) => {
+                println!("uptime   {} ms", stats.uptime_ms);
+                if let Some(idle_count) = stats.idle_count {
+                    println!("idle     {}", idle_count);
+                }
+                println!("rx       {} bytes", stats.rx_bytes);
+                println!("tx       {} bytes", stats.tx_bytes);
+                println!("dropped  {} bytes", stats.rx_dropped);
+            }
+            response => return Err(unexpected(response)),
+        },
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn simulate(verbose: bool) -> io::Result<()> {
+    use serialport::{SerialPort, TTYPort};
+
+    // `host` stays open so the board side doesn't see a hangup between two
+    // runs of pico-ctl
+    let (mut board, host) = TTYPort::pair()?;
+    board.set_timeout(SIM_POLL)?;
+    println!("simulated board on {}", host.name().unwrap_or_default());
+    sim::serve(board, sim::PRESS_PERIOD, verbose)
+}
+
+#[cfg(not(unix))]
+fn simulate(_verbose: bool) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "simulate needs pseudo-terminals",
+    ))
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let [command, options @ ..] = args.as_slice()
+        && command == "simulate"
+    {
+        let verbose = match options {
+            [] => false,
+            [option] if option == "--verbose" => true,
+            _ => {
+                eprintln!("{USAGE}");
+                return ExitCode::FAILURE;
+            }
+        };
+        return match simulate(verbose) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("{error}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+    let Some((path, command)) = args
+        .split_first()
+        .and_then(|(path, rest)| Some((path, parse(rest)?)))
+    else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+
+    // the baud rate means nothing to a USB CDC-ACM port
+    let port = match serialport::new(path, 115_200).timeout(TIMEOUT).open() {
+        Ok(port) => port,
+        Err(error) => {
+            eprintln!("can't open {path}: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match run(&mut Client::new(port), command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
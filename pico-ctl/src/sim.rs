@@ -0,0 +1,156 @@
+use std::cell::Cell;
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant};
+
+use pico_app::console::{Stats, SystemInfo, TaskInfo};
+use pico_app::input::{Action, Button, InputEvent};
+use pico_app::led::LedControl;
+use pico_app::oneshot::Oneshot;
+use pico_app::remote::{EventLog, handle};
+use pico_app::tasks::BLINK_PERIOD_MS;
+use pico_protocol::{Frame, FrameReader, Request, Response, encode_to_vec};
+
+// as many LEDs as the demo board
+const LEDS: usize = 10;
+pub const PRESS_PERIOD: Duration = Duration::from_secs(1);
+
+// Made-up but plausible numbers: the simulator runs two "tasks", one that
+// answers requests and one that presses buttons.
+struct SimInfo {
+    started: Instant,
+    idle_count: Cell<u32>,
+    rx_bytes: Cell<u32>,
+    tx_bytes: Cell<u32>,
+    requests: Cell<u32>,
+    presses: Cell<u32>,
+}
+
+impl SimInfo {
+    fn now_ms(&self) -> u32 {
+        self.started.elapsed().as_millis() as u32
+    }
+}
+
+impl SystemInfo for SimInfo {
+    fn stats(&self) -> Stats {
+        Stats {
+            uptime_ms: self.now_ms(),
+            idle_count: Some(self.idle_count.get()),
+            rx_bytes: self.rx_bytes.get(),
+            tx_bytes: self.tx_bytes.get(),
+            rx_dropped: 0,
+        }
+    }
+
+    fn task(&self, id: usize) -> Option<TaskInfo> {
+        let polls = match id {
+            0 => self.requests.get(),
+            1 => self.presses.get(),
+            _ => return None,
+        };
+        Some(TaskInfo {
+            polls,
+            finished: false,
+        })
+    }
+}
+
+fn add(counter: &Cell<u32>, amount: usize) {
+    counter.set(counter.get().wrapping_add(amount as u32));
+}
+
+// Plays the board for `pico-ctl`: answers requests on `port` the way the
+// `remote` firmware does and, while subscribed, presses and releases Left
+// and Right in turn, one change every `press_period`. `port` should time out
+// reads now and then, that's when the buttons get pressed. `verbose` logs
+// each request and its response to stderr.
+pub fn serve<P: Read + Write>(
+    mut port: P,
+    press_period: Duration,
+    verbose: bool,
+) -> io::Result<()> {
+    let active_led = Oneshot::new();
+    let control = LedControl::new(LEDS, BLINK_PERIOD_MS, &active_led);
+    // stands in for the LED task, which takes the selection and blink period
+    control.attach();
+    let events = EventLog::new();
+    let info = SimInfo {
+        started: Instant::now(),
+        idle_count: Cell::new(0),
+        rx_bytes: Cell::new(0),
+        tx_bytes: Cell::new(0),
+        requests: Cell::new(0),
+        presses: Cell::new(0),
+    };
+    let mut reader = FrameReader::new();
+    let mut last_press = Instant::now();
+    let mut received = [0; 64];
+
+    loop {
+        let len = match port.read(&mut received) {
+            Ok(0) => return Ok(()),
+            Ok(len) => len,
+            Err(error) if error.kind() == io::ErrorKind::TimedOut => {
+                add(&info.idle_count, 1);
+                0
+            }
+            Err(error) => return Err(error),
+        };
+        add(&info.rx_bytes, len);
+
+        let mut responses = Vec::new();
+        let mut bytes = &received[..len];
+        while !bytes.is_empty() {
+            let (frame, remaining) = reader.feed::<Request>(bytes);
+            bytes = remaining;
+            match frame {
+                Some(Frame::Message(request)) => {
+                    add(&info.requests, 1);
+                    let response = handle(request, &control, &info, &events);
+                    if verbose {
+                        eprintln!("{request:?} -> {response:?}");
+                    }
+                    responses.push(response);
+                }
+                Some(Frame::Invalid) => {
+                    responses.push(Response::Error(pico_protocol::Error::Malformed))
+                }
+                None => {}
+            }
+        }
+
+        if last_press.elapsed() >= press_period {
+            last_press = Instant::now();
+            let presses = info.presses.get();
+            add(&info.presses, 1);
+            let button = if (presses / 2).is_multiple_of(2) {
+                Button::Left
+            } else {
+                Button::Right
+            };
+            let action = if presses.is_multiple_of(2) {
+                Action::Press
+            } else {
+                Action::Release
+            };
+            events.push(InputEvent {
+                button,
+                action,
+                timestamp_ms: info.now_ms(),
+            });
+        }
+        while let Some(event) = events.try_next() {
+            responses.push(Response::Event(event.into()));
+        }
+
+        for response in responses {
+            let frame = encode_to_vec(&response).map_err(io::Error::other)?;
+            match port.write_all(&frame) {
+                Ok(()) => add(&info.tx_bytes, frame.len()),
+                // nobody reads, like the firmware without a USB host
+                Err(error) if error.kind() == io::ErrorKind::TimedOut => {}
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
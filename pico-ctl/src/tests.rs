@@ -0,0 +1,128 @@
+// pico-ctl against the simulated board, over a real pseudo-terminal pair
+use std::thread;
+use std::time::Duration;
+
+use pico_protocol::{Button, Error, Request, Response, TaskInfo};
+use serialport::{SerialPort, TTYPort};
+
+use crate::client::{Client, expect_ok};
+use crate::{Command, run, sim};
+
+const PRESS_PERIOD: Duration = Duration::from_millis(20);
+
+fn connect() -> Client<TTYPort> {
+    let (mut board, mut host) = TTYPort::pair().unwrap();
+    board.set_timeout(Duration::from_millis(5)).unwrap();
+    host.set_timeout(Duration::from_secs(2)).unwrap();
+    // runs until the test process exits
+    thread::spawn(move || sim::serve(board, PRESS_PERIOD, false));
+    Client::new(host)
+}
+
+#[test]
+fn ping() {
+    let mut client = connect();
+    assert_eq!(client.request(Request::Ping).unwrap(), Response::Pong);
+}
+
+#[test]
+fn led_is_range_checked() {
+    let mut client = connect();
+    expect_ok(client.request(Request::SetActiveLed(9)).unwrap()).unwrap();
+    assert_eq!(
+        client.request(Request::SetActiveLed(10)).unwrap(),
+        Response::Error(Error::NoSuchLed)
+    );
+}
+
+#[test]
+fn blink_period_is_range_checked() {
+    let mut client = connect();
+    expect_ok(
+        client
+            .request(Request::SetBlinkPeriod { period_ms: 250 })
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        client
+            .request(Request::SetBlinkPeriod { period_ms: 5 })
+            .unwrap(),
+        Response::Error(Error::InvalidBlinkPeriod)
+    );
+}
+
+#[test]
+fn tasks_end_with_none() {
+    let mut client = connect();
+    client.request(Request::Ping).unwrap();
+    assert_eq!(
+        client.request(Request::GetTask(0)).unwrap(),
+        Response::Task(Some(TaskInfo {
+            id: 0,
+            polls: 2,
+            finished: false,
+        }))
+    );
+    assert!(matches!(
+        client.request(Request::GetTask(1)).unwrap(),
+        Response::Task(Some(TaskInfo { id: 1, .. }))
+    ));
+    assert_eq!(
+        client.request(Request::GetTask(2)).unwrap(),
+        Response::Task(None)
+    );
+}
+
+#[test]
+fn stats_count_the_traffic() {
+    let mut client = connect();
+    client.request(Request::Ping).unwrap();
+    let Response::Stats(stats) = client.request(Request::GetStats).unwrap() else {
+        panic!("no stats");
+    };
+    // two one-byte requests in three-byte frames, one answered so far
+    assert_eq!(stats.rx_bytes, 6);
+    assert_eq!(stats.tx_bytes, 3);
+}
+
+#[test]
+fn events_alternate_press_and_release() {
+    const CYCLE: [(Button, bool); 4] = [
+        (Button::Left, true),
+        (Button::Left, false),
+        (Button::Right, true),
+        (Button::Right, false),
+    ];
+    let mut client = connect();
+    expect_ok(client.request(Request::SubscribeEvents(true)).unwrap()).unwrap();
+    let events: Vec<_> = (0..6).map(|_| client.next_event().unwrap()).collect();
+
+    // the simulator presses from the start, the first event seen depends on
+    // when the subscription arrived
+    let first = (events[0].button, events[0].pressed);
+    let start = CYCLE.iter().position(|&change| change == first).unwrap();
+    for (i, event) in events.iter().enumerate() {
+        assert_eq!((event.button, event.pressed), CYCLE[(start + i) % 4]);
+    }
+    assert!(
+        events
+            .windows(2)
+            .all(|pair| pair[0].timestamp_ms < pair[1].timestamp_ms)
+    );
+}
+
+#[test]
+fn every_command_runs() {
+    let mut client = connect();
+    for command in [
+        Command::Ping,
+        Command::Tasks,
+        Command::Led(3),
+        Command::Blink(250),
+        Command::Stats,
+    ] {
+        run(&mut client, command).unwrap();
+    }
+    assert!(run(&mut client, Command::Led(10)).is_err());
+}
@@ -0,0 +1,144 @@
+// The binary remote control protocol between the board and host tools.
+// Messages are postcard encoded and COBS framed, so a zero byte always ends
+// a frame and a reader that lost track just waits for the next one.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use postcard::accumulator::{CobsAccumulator, FeedResult};
+use serde::{Deserialize, Serialize};
+
+// Largest encoded frame, sentinel included. Every message fits easily.
+pub const MAX_FRAME_LEN: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Request {
+    Ping,
+    SetActiveLed(u8),
+    SetBlinkPeriod { period_ms: u32 },
+    GetStats,
+    // one task per request, `Response::Task(None)` past the last one
+    GetTask(u8),
+    // switch the stream of `Response::Event`s on or off
+    SubscribeEvents(bool),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Response {
+    Pong,
+    Ok,
+    Error(Error),
+    Stats(Stats),
+    Task(Option<TaskInfo>),
+    // sent unasked while subscribed
+    Event(ButtonEvent),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error {
+    // the request didn't decode
+    Malformed,
+    NoSuchLed,
+    InvalidBlinkPeriod,
+    // the firmware runs a mode without the LED task
+    Unsupported,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Stats {
+    pub uptime_ms: u32,
+    // `None` if the executor doesn't count it
+    pub idle_count: Option<u32>,
+    pub rx_bytes: u32,
+    pub tx_bytes: u32,
+    pub rx_dropped: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TaskInfo {
+    pub id: u8,
+    pub polls: u32,
+    pub finished: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Button {
+    Left,
+    Right,
+    Up,
+    Down,
+    Select,
+    Key(u8),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ButtonEvent {
+    pub button: Button,
+    pub pressed: bool,
+    pub timestamp_ms: u32,
+}
+
+// Encodes `message` into `buffer` as one frame, terminating zero included.
+pub fn encode<'a, T: Serialize>(
+    message: &T,
+    buffer: &'a mut [u8],
+) -> postcard::Result<&'a mut [u8]> {
+    postcard::to_slice_cobs(message, buffer)
+}
+
+// Decodes one frame in place, with or without its terminating zero.
+pub fn decode<T: for<'de> Deserialize<'de>>(frame: &mut [u8]) -> postcard::Result<T> {
+    postcard::from_bytes_cobs(frame)
+}
+
+#[cfg(feature = "std")]
+pub fn encode_to_vec<T: Serialize>(message: &T) -> postcard::Result<std::vec::Vec<u8>> {
+    postcard::to_stdvec_cobs(message)
+}
+
+// What feeding bytes to a `FrameReader` turned up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Frame<T> {
+    Message(T),
+    // a complete frame that didn't decode, or one too long to buffer
+    Invalid,
+}
+
+// Collects frames from a byte stream that arrives in arbitrary pieces.
+pub struct FrameReader {
+    accumulator: CobsAccumulator<MAX_FRAME_LEN>,
+}
+
+impl FrameReader {
+    pub const fn new() -> Self {
+        Self {
+            accumulator: CobsAccumulator::new(),
+        }
+    }
+
+    // Takes bytes up to the end of the next frame. Returns what was found,
+    // if anything, and the bytes not looked at yet.
+    pub fn feed<'a, T: for<'de> Deserialize<'de>>(
+        &mut self,
+        bytes: &'a [u8],
+    ) -> (Option<Frame<T>>, &'a [u8]) {
+        match self.accumulator.feed::<T>(bytes) {
+            FeedResult::Consumed => (None, &[]),
+            FeedResult::OverFull(remaining) | FeedResult::DeserError(remaining) => {
+                (Some(Frame::Invalid), remaining)
+            }
+            FeedResult::Success { data, remaining } => (Some(Frame::Message(data)), remaining),
+        }
+    }
+}
+
+impl Default for FrameReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,170 @@
+use pico_protocol::{
+    Button, ButtonEvent, Error, Frame, FrameReader, MAX_FRAME_LEN, Request, Response, Stats,
+    TaskInfo, decode, encode,
+};
+
+fn requests() -> [Request; 7] {
+    [
+        Request::Ping,
+        Request::SetActiveLed(9),
+        Request::SetBlinkPeriod { period_ms: 250 },
+        Request::GetStats,
+        Request::GetTask(3),
+        Request::SubscribeEvents(true),
+        Request::SubscribeEvents(false),
+    ]
+}
+
+fn responses() -> [Response; 14] {
+    [
+        Response::Pong,
+        Response::Ok,
+        Response::Error(Error::Malformed),
+        Response::Error(Error::NoSuchLed),
+        Response::Error(Error::InvalidBlinkPeriod),
+        Response::Error(Error::Unsupported),
+        Response::Stats(Stats {
+            uptime_ms: u32::MAX,
+            idle_count: Some(1),
+            rx_bytes: 0,
+            tx_bytes: 1 << 31,
+            rx_dropped: 5,
+        }),
+        Response::Stats(Stats {
+            idle_count: None,
+            ..Stats::default()
+        }),
+        Response::Task(Some(TaskInfo {
+            id: 255,
+            polls: u32::MAX,
+            finished: true,
+        })),
+        Response::Task(None),
+        Response::Event(ButtonEvent {
+            button: Button::Left,
+            pressed: true,
+            timestamp_ms: 0,
+        }),
+        Response::Event(ButtonEvent {
+            button: Button::Select,
+            pressed: false,
+            timestamp_ms: 123_456,
+        }),
+        Response::Event(ButtonEvent {
+            button: Button::Key(b'#'),
+            pressed: true,
+            timestamp_ms: u32::MAX,
+        }),
+        Response::Event(ButtonEvent {
+            button: Button::Down,
+            pressed: false,
+            timestamp_ms: 7,
+        }),
+    ]
+}
+
+// every message as one frame, back to back
+fn stream<T: serde::Serialize>(messages: &[T]) -> Vec<u8> {
+    let mut stream = Vec::new();
+    for message in messages {
+        let mut buffer = [0; MAX_FRAME_LEN];
+        stream.extend_from_slice(encode(message, &mut buffer).unwrap());
+    }
+    stream
+}
+
+// feeds `chunks` one after the other and collects every frame found
+fn read_all<T: for<'de> serde::Deserialize<'de>>(chunks: &[&[u8]]) -> Vec<Frame<T>> {
+    let mut reader = FrameReader::new();
+    let mut frames = Vec::new();
+    for chunk in chunks {
+        let mut bytes = *chunk;
+        while !bytes.is_empty() {
+            let (frame, remaining) = reader.feed(bytes);
+            frames.extend(frame);
+            bytes = remaining;
+        }
+    }
+    frames
+}
+
+#[test]
+fn frames_end_in_the_only_zero() {
+    for request in requests() {
+        let mut buffer = [0; MAX_FRAME_LEN];
+        let frame = encode(&request, &mut buffer).unwrap();
+        let (last, body) = frame.split_last().unwrap();
+        assert_eq!(*last, 0);
+        assert!(!body.contains(&0));
+    }
+}
+
+#[test]
+fn every_request_round_trips() {
+    for request in requests() {
+        let mut buffer = [0; MAX_FRAME_LEN];
+        let frame = encode(&request, &mut buffer).unwrap();
+        assert_eq!(decode::<Request>(frame).unwrap(), request);
+    }
+}
+
+#[test]
+fn every_response_round_trips() {
+    for response in responses() {
+        let mut buffer = [0; MAX_FRAME_LEN];
+        let frame = encode(&response, &mut buffer).unwrap();
+        assert_eq!(decode::<Response>(frame).unwrap(), response);
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn vec_encoding_matches_slice_encoding() {
+    for response in responses() {
+        let mut buffer = [0; MAX_FRAME_LEN];
+        let frame = encode(&response, &mut buffer).unwrap();
+        assert_eq!(pico_protocol::encode_to_vec(&response).unwrap(), frame);
+    }
+}
+
+#[test]
+fn reader_finds_concatenated_frames() {
+    let frames = read_all::<Response>(&[&stream(&responses())]);
+    let expected: Vec<_> = responses().into_iter().map(Frame::Message).collect();
+    assert_eq!(frames, expected);
+}
+
+#[test]
+fn reader_joins_frames_split_at_any_byte() {
+    let stream = stream(&requests());
+    let chunks: Vec<&[u8]> = stream.chunks(1).collect();
+    let expected: Vec<_> = requests().into_iter().map(Frame::Message).collect();
+    assert_eq!(read_all::<Request>(&chunks), expected);
+
+    for split in 0..stream.len() {
+        let (first, second) = stream.split_at(split);
+        assert_eq!(read_all::<Request>(&[first, second]), expected);
+    }
+}
+
+#[test]
+fn reader_reports_garbage_and_resyncs() {
+    // variant 9 doesn't exist
+    let mut bytes = vec![0x02, 0x09, 0x00];
+    bytes.extend(stream(&[Request::Ping]));
+    assert_eq!(
+        read_all::<Request>(&[&bytes]),
+        [Frame::Invalid, Frame::Message(Request::Ping)]
+    );
+}
+
+#[test]
+fn reader_reports_oversize_frames_and_resyncs() {
+    let mut bytes = vec![0x01; 2 * MAX_FRAME_LEN];
+    bytes.push(0);
+    bytes.extend(stream(&[Request::GetStats]));
+    assert_eq!(
+        read_all::<Request>(&[&bytes]),
+        [Frame::Invalid, Frame::Message(Request::GetStats)]
+    );
+}